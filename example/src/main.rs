@@ -48,7 +48,7 @@ fn main() {
             gfx.new_batch(TextureBatchOptions::new(kirby_texture_2, Some(target)))
                 .unwrap()
                 .draw(DrawOptions {
-                    dest_rect: Some(Rect::new(20, 20, 60, 60)),
+                    dest_rect: Some(Rect::new(20, 20, 60, 60).into()),
                     rotation_angle: angle,
                     ..Default::default()
                 });
@@ -58,7 +58,7 @@ fn main() {
                 .unwrap()
                 .draw(Default::default());
 
-            gfx.present();
+            gfx.present().unwrap();
             *control_flow = ControlFlow::Poll;
         }
         Event::WindowEvent { event, window_id } if window.id() == window_id => match event {
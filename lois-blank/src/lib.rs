@@ -1,16 +1,25 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use lois::{
     backend::Backend,
-    commons::ViewSize,
+    commons::{Rect, ViewSize},
+    error::{Error, Result},
     graphics::DrawCommand,
+    mesh::MeshVertex,
+    postprocess::BuiltinShader,
     quad::Quad,
-    texture::{Texture, TextureFormat, TextureLoadOptions, TextureQuery, TextureUsage},
+    shader::{Shader, ShaderLoadOptions},
+    texture::{SamplerOptions, Texture, TextureFormat, TextureLoadOptions, TextureQuery, TextureUsage},
 };
 
 pub struct BackendBlank {
     viewport_size: ViewSize,
     textures: HashMap<u32, TextureBlank>,
     next_texture: Texture,
+    shaders: HashSet<u32>,
+    next_shader: Shader,
+    scissors: Vec<Option<Rect>>,
+    commands: Vec<DrawCommand>,
+    screen_capture: bool,
 }
 
 struct TextureBlank {
@@ -18,6 +27,14 @@ struct TextureBlank {
     size: ViewSize,
     usage: TextureUsage,
     format: TextureFormat,
+    sampler: SamplerOptions,
+    data: Vec<u8>,
+}
+
+impl Default for BackendBlank {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BackendBlank {
@@ -26,14 +43,67 @@ impl BackendBlank {
             viewport_size: ViewSize { width: 860, height: 640 },
             next_texture: Texture::new(0),
             textures: HashMap::with_capacity(100),
+            shaders: HashSet::new(),
+            next_shader: Shader::new(0),
+            scissors: Vec::new(),
+            commands: Vec::new(),
+            screen_capture: false,
         }
     }
+
+    pub fn scissors(&self) -> &[Option<Rect>] {
+        &self.scissors
+    }
+
+    // Commands received by the last `present` call.
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
 }
 
 impl Backend for BackendBlank {
-    fn present(&mut self, _commands: &[DrawCommand], _quads: &[Quad]) {}
+    fn present(
+        &mut self,
+        commands: &[DrawCommand],
+        _quads: &[Quad],
+        _vertices: &[MeshVertex],
+        _indices: &[u32],
+    ) -> Result<()> {
+        self.scissors.clear();
+        self.commands = commands.to_vec();
+        for command in commands {
+            let (target, scissor) = match command {
+                DrawCommand::DrawTextureBatch(command) => {
+                    if let Some(shader) = command.shader.filter(|shader| !self.shaders.contains(&shader.id())) {
+                        return Err(Error::ShaderNotFound(shader));
+                    }
+                    (command.target, command.scissor)
+                }
+                DrawCommand::DrawShapes(command) => (command.target, command.scissor),
+                DrawCommand::DrawMesh(command) => (command.target, command.scissor),
+                DrawCommand::Clear(_) => continue,
+            };
+            let size = match target {
+                Some(target) => self.query_texture(target).ok_or(Error::TargetNotFound(target))?.size,
+                None => self.viewport_size,
+            };
+            self.scissors.push(scissor.map(|scissor| scissor.intersection(size.into()).unwrap_or_default()));
+        }
+        Ok(())
+    }
 
-    fn load_texture(&mut self, options: TextureLoadOptions) -> Result<Texture, String> {
+    fn load_texture(&mut self, options: TextureLoadOptions) -> Result<Texture> {
+        if options.usage == TextureUsage::RenderTarget && options.format != TextureFormat::default() {
+            return Err(Error::UnsupportedFormat(options.format));
+        }
+        let len = options.format.bytes_per_pixel() * options.size.width as usize * options.size.height as usize;
+        let data = match options.data {
+            Some(data) if data.len() < len => {
+                return Err(Error::InvalidTextureData { expected: len, actual: data.len() })
+            }
+            Some(data) => data[..len].to_vec(),
+            None => vec![0; len],
+        };
         self.textures.insert(
             self.next_texture.id(),
             TextureBlank {
@@ -41,6 +111,8 @@ impl Backend for BackendBlank {
                 format: options.format,
                 size: options.size,
                 usage: options.usage,
+                sampler: options.sampler,
+                data,
             },
         );
         let texture = self.next_texture;
@@ -48,16 +120,77 @@ impl Backend for BackendBlank {
         Ok(texture)
     }
 
+    fn update_texture(&mut self, texture: Texture, rect: Option<Rect>, data: &[u8]) -> Result<()> {
+        let texture = self.textures.get_mut(&texture.id()).ok_or(Error::TextureNotFound(texture))?;
+        let rect = texture_rect(texture.size, rect)?;
+        if rect.w == 0 || rect.h == 0 {
+            return Ok(());
+        }
+        let row_len = texture.format.bytes_per_pixel() * rect.w as usize;
+        let expected = row_len * rect.h as usize;
+        if data.len() < expected {
+            return Err(Error::InvalidTextureData { expected, actual: data.len() });
+        }
+        for (row, source) in data.chunks_exact(row_len).take(rect.h as usize).enumerate() {
+            let start = texture.offset(rect.x as usize, rect.y as usize + row);
+            texture.data[start..start + row_len].copy_from_slice(source);
+        }
+        Ok(())
+    }
+
+    fn read_texture(&mut self, texture: Texture, rect: Option<Rect>) -> Result<Vec<u8>> {
+        let texture = self.textures.get(&texture.id()).ok_or(Error::TextureNotFound(texture))?;
+        let rect = texture_rect(texture.size, rect)?;
+        let row_len = texture.format.bytes_per_pixel() * rect.w as usize;
+        let mut data = Vec::with_capacity(row_len * rect.h as usize);
+        for row in 0..rect.h as usize {
+            let start = texture.offset(rect.x as usize, rect.y as usize + row);
+            data.extend_from_slice(&texture.data[start..start + row_len]);
+        }
+        Ok(data)
+    }
+
+    fn read_screen(&mut self, rect: Option<Rect>) -> Result<Vec<u8>> {
+        if !self.screen_capture {
+            return Err(Error::ScreenCaptureDisabled);
+        }
+        let Rect { w, h, .. } = texture_rect(self.viewport_size, rect)?;
+        Ok(vec![0; TextureFormat::default().bytes_per_pixel() * w as usize * h as usize])
+    }
+
+    fn set_screen_capture(&mut self, enabled: bool) {
+        self.screen_capture = enabled;
+    }
+
     fn unload_texture(&mut self, texture: Texture) {
         self.textures.remove(&texture.id());
     }
 
-    fn query_texture(&self, texture: Texture) -> Option<TextureQuery> {
+    fn load_shader(&mut self, _options: ShaderLoadOptions) -> Result<Shader> {
+        let shader = self.next_shader;
+        self.shaders.insert(shader.id());
+        self.next_shader = Shader::new(shader.id() + 1);
+        Ok(shader)
+    }
+
+    fn load_builtin_shader(&mut self, _shader: BuiltinShader) -> Result<Shader> {
+        let shader = self.next_shader;
+        self.shaders.insert(shader.id());
+        self.next_shader = Shader::new(shader.id() + 1);
+        Ok(shader)
+    }
+
+    fn unload_shader(&mut self, shader: Shader) {
+        self.shaders.remove(&shader.id());
+    }
+
+    fn query_texture(&self, texture: Texture) -> Option<TextureQuery<'_>> {
         let texture = self.textures.get(&texture.id())?;
         Some(TextureQuery {
-            name: texture.name.as_ref().map(|s| s.as_str()),
+            name: texture.name.as_deref(),
             format: texture.format,
             usage: texture.usage,
+            sampler: texture.sampler,
             size: texture.size,
         })
     }
@@ -70,3 +203,22 @@ impl Backend for BackendBlank {
         self.viewport_size
     }
 }
+
+impl TextureBlank {
+    fn offset(&self, x: usize, y: usize) -> usize {
+        self.format.bytes_per_pixel() * (y * self.size.width as usize + x)
+    }
+}
+
+fn texture_rect(size: ViewSize, rect: Option<Rect>) -> Result<Rect> {
+    let rect = rect.unwrap_or_else(|| size.into());
+    let fits = rect.x >= 0
+        && rect.y >= 0
+        && rect.x as u64 + rect.w as u64 <= size.width as u64
+        && rect.y as u64 + rect.h as u64 <= size.height as u64;
+    if fits {
+        Ok(rect)
+    } else {
+        Err(Error::InvalidTextureRect(rect))
+    }
+}
@@ -0,0 +1,91 @@
+use lois::{
+    atlas::{pack_atlas, AtlasImage, AtlasOptions},
+    commons::{Rect, ViewSize},
+    error::Error,
+    graphics::Graphics,
+};
+use lois_blank::BackendBlank;
+
+fn image_data(size: ViewSize, value: u8) -> Vec<u8> {
+    vec![value; 4 * size.width as usize * size.height as usize]
+}
+
+#[test]
+fn overflowing_images_go_to_new_pages() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+    let size = ViewSize::new(8, 8);
+    let data: Vec<Vec<u8>> = (1..=3).map(|value| image_data(size, value)).collect();
+    let images: Vec<AtlasImage> = data.iter().map(|data| AtlasImage::new(data, size)).collect();
+
+    let atlas = pack_atlas(
+        &mut graphics,
+        &images,
+        AtlasOptions {
+            page_size: size,
+            padding: 0,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(atlas.pages.len(), 3);
+    for (sub_texture, data) in atlas.sub_textures.iter().zip(data.iter()) {
+        assert_eq!(sub_texture.rect, Rect::new(0, 0, 8, 8));
+        assert_eq!(&graphics.read_texture(sub_texture.texture, None).unwrap(), data);
+    }
+}
+
+#[test]
+fn images_are_copied_inside_their_padding() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+    let size = ViewSize::new(2, 2);
+    let first = image_data(size, 1);
+    let second = image_data(size, 2);
+
+    let atlas = pack_atlas(
+        &mut graphics,
+        &[AtlasImage::new(&first, size), AtlasImage::new(&second, size)],
+        AtlasOptions {
+            page_size: ViewSize::new(8, 8),
+            padding: 1,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(atlas.pages.len(), 1);
+    let rects: Vec<Rect> = atlas.sub_textures.iter().map(|sub_texture| sub_texture.rect).collect();
+    assert_eq!(rects, vec![Rect::new(1, 1, 2, 2), Rect::new(4, 1, 2, 2)]);
+    assert_eq!(graphics.read_texture(atlas.pages[0], Some(rects[0])).unwrap(), first);
+    assert_eq!(graphics.read_texture(atlas.pages[0], Some(rects[1])).unwrap(), second);
+    assert_eq!(graphics.read_texture(atlas.pages[0], Some(Rect::new(3, 1, 1, 2))).unwrap(), vec![0; 8]);
+}
+
+#[test]
+fn images_larger_than_a_page_are_rejected() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+    let size = ViewSize::new(7, 7);
+    let data = image_data(size, 1);
+
+    let result = pack_atlas(
+        &mut graphics,
+        &[AtlasImage::new(&data, size)],
+        AtlasOptions {
+            page_size: ViewSize::new(8, 8),
+            padding: 1,
+            ..Default::default()
+        },
+    );
+
+    assert!(matches!(result, Err(Error::AtlasImageTooLarge(too_large)) if too_large == size));
+}
+
+#[test]
+fn short_image_data_is_rejected() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+    let data = vec![0; 4];
+
+    let result = pack_atlas(&mut graphics, &[AtlasImage::new(&data, ViewSize::new(2, 2))], AtlasOptions::default());
+
+    assert!(matches!(result, Err(Error::InvalidTextureData { expected: 16, actual: 4 })));
+}
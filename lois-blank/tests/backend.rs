@@ -0,0 +1,28 @@
+use lois::{
+    backend::Backend,
+    commons::{Rect, ViewSize},
+    error::Error,
+    texture::TextureLoadOptions,
+};
+use lois_blank::BackendBlank;
+
+#[test]
+fn out_of_bounds_rects_are_rejected() {
+    let mut backend = BackendBlank::new();
+    let texture = backend
+        .load_texture(TextureLoadOptions {
+            size: ViewSize::new(4, 4),
+            ..Default::default()
+        })
+        .unwrap();
+    backend.set_screen_capture(true);
+
+    for rect in [Rect::new(2, 2, 3, 1), Rect::new(0, 4, 1, 1), Rect::new(-1, 0, 1, 1)] {
+        let data = vec![0; 4 * rect.w as usize * rect.h as usize];
+        assert!(matches!(backend.update_texture(texture, Some(rect), &data), Err(Error::InvalidTextureRect(r)) if r == rect));
+        assert!(matches!(backend.read_texture(texture, Some(rect)), Err(Error::InvalidTextureRect(r)) if r == rect));
+    }
+    let viewport = backend.viewport();
+    let rect = Rect::new(0, 0, viewport.width + 1, 1);
+    assert!(matches!(backend.read_screen(Some(rect)), Err(Error::InvalidTextureRect(r)) if r == rect));
+}
@@ -0,0 +1,120 @@
+use lois::{
+    batch::TextureBatchOptions,
+    commons::{Point, Rect, ViewSize},
+    error::Error,
+    graphics::{DrawCommand, DrawOptions, Graphics},
+    texture::{SamplerOptions, Texture, TextureLoadOptions},
+    transform::Transform2D,
+};
+use lois_blank::BackendBlank;
+
+fn load_texture(graphics: &mut Graphics<BackendBlank>, width: u32, height: u32) -> Texture {
+    let data: Vec<u8> = (0..4 * width * height).map(|byte| byte as u8).collect();
+    graphics
+        .load_texture(TextureLoadOptions {
+            data: Some(&data),
+            size: ViewSize::new(width, height),
+            ..Default::default()
+        })
+        .unwrap()
+}
+
+#[test]
+fn update_texture_ignores_zero_size_rects() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+    let texture = load_texture(&mut graphics, 4, 4);
+    let before = graphics.read_texture(texture, None).unwrap();
+
+    graphics.update_texture(texture, Some(Rect::new(1, 1, 0, 3)), &[]).unwrap();
+    graphics.update_texture(texture, Some(Rect::new(2, 4, 2, 0)), &[]).unwrap();
+
+    assert_eq!(graphics.read_texture(texture, None).unwrap(), before);
+}
+
+#[test]
+fn update_texture_rejects_zero_size_rects_out_of_bounds() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+    let texture = load_texture(&mut graphics, 4, 4);
+
+    assert!(graphics.update_texture(texture, Some(Rect::new(5, 0, 0, 1)), &[]).is_err());
+}
+
+#[test]
+fn update_texture_writes_rect() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+    let texture = load_texture(&mut graphics, 2, 2);
+
+    graphics.update_texture(texture, Some(Rect::new(1, 0, 1, 2)), &[9; 8]).unwrap();
+
+    let data = graphics.read_texture(texture, None).unwrap();
+    assert_eq!(&data[0..4], &[0, 1, 2, 3]);
+    assert_eq!(&data[4..8], &[9; 4]);
+    assert_eq!(&data[8..12], &[8, 9, 10, 11]);
+    assert_eq!(&data[12..16], &[9; 4]);
+}
+
+#[test]
+fn read_screen_requires_screen_capture() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+
+    assert!(matches!(graphics.read_screen(None), Err(Error::ScreenCaptureDisabled)));
+
+    graphics.set_screen_capture(true);
+    assert_eq!(graphics.read_screen(Some(Rect::new(0, 0, 2, 3))).unwrap().len(), 4 * 2 * 3);
+
+    graphics.set_screen_capture(false);
+    assert!(matches!(graphics.read_screen(None), Err(Error::ScreenCaptureDisabled)));
+}
+
+fn batch_samplers(graphics: &mut Graphics<BackendBlank>) -> Vec<Option<SamplerOptions>> {
+    graphics
+        .backend()
+        .commands()
+        .iter()
+        .filter_map(|command| match command {
+            DrawCommand::DrawTextureBatch(command) => Some(command.sampler),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn default_sampler_override_batches_with_no_override() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+    let texture = load_texture(&mut graphics, 4, 4);
+    let sampler = graphics.query_texture(texture).unwrap().sampler;
+
+    graphics.new_batch(TextureBatchOptions::new(texture, None)).unwrap().draw(DrawOptions::default());
+    graphics
+        .new_batch(TextureBatchOptions::new(texture, None).with_sampler(sampler))
+        .unwrap()
+        .draw(DrawOptions::default());
+    graphics.present().unwrap();
+    assert_eq!(batch_samplers(&mut graphics), vec![None]);
+
+    graphics.new_batch(TextureBatchOptions::new(texture, None)).unwrap().draw(DrawOptions::default());
+    graphics
+        .new_batch(TextureBatchOptions::new(texture, None).with_sampler(SamplerOptions::linear()))
+        .unwrap()
+        .draw(DrawOptions::default());
+    graphics.present().unwrap();
+    assert_eq!(batch_samplers(&mut graphics), vec![None, Some(SamplerOptions::linear())]);
+}
+
+#[test]
+fn nested_transforms_pop_in_reverse_order() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+    let translation = Transform2D::from_translation(10.0, 20.0);
+    let scale = Transform2D::from_scale(2.0, 3.0);
+
+    graphics.push_transform(translation);
+    graphics.push_transform(scale);
+    assert_eq!(graphics.transform(), translation * scale);
+    assert_eq!(graphics.transform().transform_point(Point::new(1.0, 1.0)), Point::new(12.0, 23.0));
+
+    assert_eq!(graphics.pop_transform(), Some(scale));
+    assert_eq!(graphics.transform(), translation);
+    assert_eq!(graphics.pop_transform(), Some(translation));
+    assert_eq!(graphics.transform(), Transform2D::IDENTITY);
+    assert_eq!(graphics.pop_transform(), None);
+}
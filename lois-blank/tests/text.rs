@@ -0,0 +1,40 @@
+use lois::{
+    commons::{Color, Point},
+    graphics::{DrawCommand, Graphics},
+    text::{draw_text, BitmapFont, FontDescriptor},
+};
+use lois_blank::BackendBlank;
+
+const DESCRIPTOR: &str = "\
+common lineHeight=10 base=8 scaleW=16 scaleH=16 pages=2
+page id=0 file=\"a.png\"
+page id=1 file=\"b.png\"
+char id=65 x=0 y=0 width=4 height=8 xoffset=0 yoffset=0 xadvance=5 page=0
+char id=66 x=4 y=0 width=4 height=8 xoffset=0 yoffset=0 xadvance=5 page=0
+char id=67 x=0 y=0 width=4 height=8 xoffset=0 yoffset=0 xadvance=5 page=1
+char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=3 page=1
+";
+
+#[test]
+fn consecutive_glyphs_on_a_page_share_a_batch() {
+    let mut graphics = Graphics::new(BackendBlank::new());
+    let descriptor = FontDescriptor::parse_text(DESCRIPTOR).unwrap();
+    let font = BitmapFont::load(&mut graphics, &descriptor, |_| Ok(vec![0; 4 * 16 * 16])).unwrap();
+
+    draw_text(&mut graphics, None, &font, "AB C A", Point::new(0.0, 0.0), Color::WHITE, 1.0).unwrap();
+    graphics.present().unwrap();
+
+    let batches: Vec<_> = graphics
+        .backend()
+        .commands()
+        .iter()
+        .filter_map(|command| match command {
+            DrawCommand::DrawTextureBatch(command) => Some((command.texture, command.range.len())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        batches,
+        vec![(font.pages()[0], 2), (font.pages()[1], 1), (font.pages()[0], 1)]
+    );
+}
@@ -19,6 +19,16 @@ fn main() {
             None,
         )
         .unwrap();
+    let mesh_vs_spirv = compiler
+        .compile_into_spirv(
+            include_str!("./shaders/mesh.vert"),
+            shaderc::ShaderKind::Vertex,
+            "mesh.vert",
+            "main",
+            None,
+        )
+        .unwrap();
     std::fs::write("shaders/out/shader.vert.spv", vs_spirv.as_binary_u8()).unwrap();
     std::fs::write("shaders/out/shader.frag.spv", fs_spirv.as_binary_u8()).unwrap();
+    std::fs::write("shaders/out/mesh.vert.spv", mesh_vs_spirv.as_binary_u8()).unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,28 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D tex_view;
+layout(set = 0, binding = 1) uniform sampler tex_sampler;
+
+layout(location=0) in vec2 tex_coords;
+layout(location=1) in vec4 tint;
+
+layout(location=0) out vec4 frag_color;
+
+layout(set = 2, binding = 0)
+uniform Params {
+    vec2 direction;
+    vec2 texel_size;
+    float radius;
+};
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    vec2 offset = direction * texel_size * max(radius, 1.0) / 4.0;
+    vec4 color = texture(sampler2D(tex_view, tex_sampler), tex_coords) * weights[0];
+    for (int i = 1; i < 5; i++) {
+        color += texture(sampler2D(tex_view, tex_sampler), tex_coords + offset * float(i)) * weights[i];
+        color += texture(sampler2D(tex_view, tex_sampler), tex_coords - offset * float(i)) * weights[i];
+    }
+    frag_color = color * tint;
+}
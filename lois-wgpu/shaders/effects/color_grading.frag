@@ -0,0 +1,25 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D tex_view;
+layout(set = 0, binding = 1) uniform sampler tex_sampler;
+
+layout(location=0) in vec2 tex_coords;
+layout(location=1) in vec4 tint;
+
+layout(location=0) out vec4 frag_color;
+
+layout(set = 2, binding = 0)
+uniform Params {
+    vec4 tint_color;
+    float brightness;
+    float contrast;
+    float saturation;
+};
+
+void main() {
+    vec4 color = texture(sampler2D(tex_view, tex_sampler), tex_coords);
+    vec3 rgb = (color.rgb + brightness - 0.5) * contrast + 0.5;
+    float luma = dot(rgb, vec3(0.2126, 0.7152, 0.0722));
+    rgb = mix(vec3(luma), rgb, saturation) * tint_color.rgb;
+    frag_color = vec4(clamp(rgb, 0.0, 1.0), color.a) * tint;
+}
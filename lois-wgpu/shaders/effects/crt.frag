@@ -0,0 +1,29 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D tex_view;
+layout(set = 0, binding = 1) uniform sampler tex_sampler;
+
+layout(location=0) in vec2 tex_coords;
+layout(location=1) in vec4 tint;
+
+layout(location=0) out vec4 frag_color;
+
+layout(set = 2, binding = 0)
+uniform Params {
+    vec2 resolution;
+    float curvature;
+    float scanline_intensity;
+};
+
+void main() {
+    vec2 uv = tex_coords * 2.0 - 1.0;
+    uv *= 1.0 + curvature * uv.yx * uv.yx;
+    uv = uv * 0.5 + 0.5;
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        frag_color = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+    vec4 color = texture(sampler2D(tex_view, tex_sampler), uv);
+    float scanline = 0.5 + 0.5 * sin(uv.y * resolution.y * 3.14159265);
+    frag_color = vec4(color.rgb * mix(1.0, scanline, scanline_intensity), color.a) * tint;
+}
@@ -0,0 +1,19 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D tex_view;
+layout(set = 0, binding = 1) uniform sampler tex_sampler;
+
+layout(location=0) in vec2 tex_coords;
+layout(location=1) in vec4 tint;
+
+layout(location=0) out vec4 frag_color;
+
+layout(set = 2, binding = 0)
+uniform Params {
+    float intensity;
+};
+
+void main() {
+    vec4 color = texture(sampler2D(tex_view, tex_sampler), tex_coords);
+    frag_color = vec4(color.rgb * intensity, 1.0);
+}
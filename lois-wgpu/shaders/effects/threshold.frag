@@ -0,0 +1,21 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D tex_view;
+layout(set = 0, binding = 1) uniform sampler tex_sampler;
+
+layout(location=0) in vec2 tex_coords;
+layout(location=1) in vec4 tint;
+
+layout(location=0) out vec4 frag_color;
+
+layout(set = 2, binding = 0)
+uniform Params {
+    float threshold;
+};
+
+void main() {
+    vec4 color = texture(sampler2D(tex_view, tex_sampler), tex_coords);
+    float brightness = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+    float factor = max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+    frag_color = vec4(color.rgb * factor, 1.0);
+}
@@ -0,0 +1,22 @@
+#version 450
+
+// constants
+layout(set = 1, binding = 0)
+uniform Uniforms {
+    mat4 view;
+};
+
+// in
+layout(location=0) in vec2 position;
+layout(location=1) in vec2 uv;
+layout(location=2) in vec4 color;
+
+// out
+layout(location=0) out vec2 tex_coords;
+layout(location=1) out vec4 tint;
+
+void main() {
+    tex_coords = uv;
+    tint = color;
+    gl_Position = view * vec4(position, 0.0, 1.0);
+}
@@ -3,10 +3,18 @@
 layout(set = 0, binding = 0) uniform texture2D tex_view;
 layout(set = 0, binding = 1) uniform sampler tex_sampler;
 
+layout(set = 0, binding = 2)
+uniform Swizzle {
+    mat4 swizzle_channels;
+    vec4 swizzle_offset;
+};
+
 layout(location=0) in vec2 tex_coords;
+layout(location=1) in vec4 tint;
 
 layout(location=0) out vec4 frag_color;
 
 void main() {
-    frag_color = texture(sampler2D(tex_view, tex_sampler), tex_coords);
+    vec4 color = swizzle_channels * texture(sampler2D(tex_view, tex_sampler), tex_coords) + swizzle_offset;
+    frag_color = color * tint;
 }
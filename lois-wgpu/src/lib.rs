@@ -1,10 +1,10 @@
 use winit::window::Window;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
-use raw_window_handle::HasRawWindowHandle;
 use wgpu::util::DeviceExt;
 
-use lois::{backend::Backend, commons::{Color, ViewSize}, graphics::DrawCommand, quad::Quad, texture::{Texture, TextureFormat, TextureLoadOptions, TextureQuery, TextureUsage}};
+use lois::{backend::Backend, batch::BlendMode, camera::Camera2D, commons::{Color, FRect, Rect, ViewSize}, error::{Error, Result}, graphics::{DrawCommand, DrawOptions}, mesh::MeshVertex, postprocess::BuiltinShader, quad::Quad, shader::{Shader, ShaderLoadOptions, ShaderSource, UniformBlock, MAX_UNIFORM_TEXTURES}, texture::{AddressMode, FilterMode, SamplerOptions, Texture, TextureFormat, TextureLoadOptions, TextureQuery, TextureUsage}, transform::Transform2D};
 
 pub struct BackendWgpu {
     viewport_size: ViewSize,
@@ -14,20 +14,70 @@ pub struct BackendWgpu {
     queue: wgpu::Queue,
     swap_chain: wgpu::SwapChain,
 
-    render_pipeline: wgpu::RenderPipeline,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    vertex_module: wgpu::ShaderModule,
+    fragment_module: wgpu::ShaderModule,
+    render_pipelines: HashMap<(Option<Shader>, BlendMode), wgpu::RenderPipeline>,
+    mesh_pipelines: HashMap<BlendMode, wgpu::RenderPipeline>,
 
     _uniforms_layout: wgpu::BindGroupLayout,
+
+    uniform_block_layout: wgpu::BindGroupLayout,
+    default_uniform_block: (wgpu::Buffer, wgpu::BindGroup),
+    uniform_blocks: Vec<UniformBlockSlot>,
     uniforms_bind_group: wgpu::BindGroup,
 
     texture_layout: wgpu::BindGroupLayout,
     textures: HashMap<u32, TextureWgpu>,
+    samplers: HashMap<SamplerOptions, wgpu::Sampler>,
+    sampler_bind_groups: HashMap<(u32, SamplerOptions), wgpu::BindGroup>,
     next_texture: u32,
+    white_texture: TextureWgpu,
+    screen: Option<TextureWgpu>,
+    screen_quad: wgpu::Buffer,
+
+    shaders: HashMap<u32, ShaderWgpu>,
+    next_shader: u32,
+    error_scope: ErrorScope,
 
     uniforms_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     vertex_buffer: wgpu::Buffer,
-    instance_buffer: Option<wgpu::Buffer>,
-    instance_buffer_capacity: usize,
+    instance_buffer: DynamicBuffer,
+    mesh_vertex_buffer: DynamicBuffer,
+    mesh_index_buffer: DynamicBuffer,
+}
+
+// wgpu 0.7 has no error scopes and its default handler panics on any validation error, so
+// errors raised while a scope is capturing are collected instead, letting invalid user
+// shaders be reported as errors.
+#[derive(Clone, Default)]
+struct ErrorScope(Arc<Mutex<Option<Vec<String>>>>);
+
+struct DynamicBuffer {
+    label: &'static str,
+    usage: wgpu::BufferUsage,
+    buffer: Option<wgpu::Buffer>,
+    capacity: usize,
+}
+
+struct UniformBlockSlot {
+    textures: Vec<u32>,
+    capacity: usize,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+struct PassTarget<'a> {
+    attachment: &'a wgpu::TextureView,
+    size: ViewSize,
+    viewport: FRect,
+    scissor: Option<Rect>,
+}
+
+struct ShaderWgpu {
+    vertex: Option<wgpu::ShaderModule>,
+    fragment: wgpu::ShaderModule,
 }
 
 struct TextureWgpu {
@@ -35,10 +85,11 @@ struct TextureWgpu {
     size: ViewSize,
     format: TextureFormat,
     usage: TextureUsage,
+    sampler_options: SamplerOptions,
 
-    _texture: wgpu::Texture,
+    texture: wgpu::Texture,
     view: wgpu::TextureView,
-    _sampler: wgpu::Sampler,
+    swizzle_buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
 }
 
@@ -52,6 +103,13 @@ struct Uniforms {
     view: [[f32; 4]; 4],
 }
 
+#[repr(C)]
+#[derive(Clone, Debug)]
+struct Swizzle {
+    channels: [[f32; 4]; 4],
+    offset: [f32; 4],
+}
+
 const QUAD_VERTICES: &[Vertex] = &[
     Vertex(0.0, 0.0),
     Vertex(1.0, 0.0),
@@ -61,66 +119,118 @@ const QUAD_VERTICES: &[Vertex] = &[
 
 const QUAD_INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
 
+const BLEND_MODES: &[BlendMode] = &[
+    BlendMode::Alpha,
+    BlendMode::Additive,
+    BlendMode::Multiply,
+    BlendMode::Premultiplied,
+    BlendMode::Replace,
+];
+
 impl Backend for BackendWgpu {
-    fn present(&mut self, commands: &[DrawCommand], quads: &[Quad]) {
+    fn present(
+        &mut self,
+        commands: &[DrawCommand],
+        quads: &[Quad],
+        vertices: &[MeshVertex],
+        indices: &[u32],
+    ) -> Result<()> {
         let current_frame = match self.swap_chain.get_current_frame() {
             Ok(current_frame) => current_frame,
-            Err(wgpu::SwapChainError::OutOfMemory) => {
-                self.resize_viewport(self.viewport_size);
-                return
+            Err(wgpu::SwapChainError::Timeout) => return Ok(()),
+            // Routinely happens on resize or minimize: recreate the swap chain and skip the frame.
+            Err(wgpu::SwapChainError::Outdated) | Err(wgpu::SwapChainError::Lost) => {
+                self.swap_chain = create_swap_chain(
+                    &self.device,
+                    &self.surface,
+                    self.viewport_size.width,
+                    self.viewport_size.height,
+                );
+                return Ok(());
             }
-            _ => return
+            Err(err) => return Err(Error::SurfaceLost(Box::new(err))),
         };
 
-        let view = &current_frame.output.view;
-
-        if self.instance_buffer_capacity < quads.len() || self.instance_buffer.is_none() {
-            self.instance_buffer = Some(self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
-                usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
-                contents: quads.as_bytes(),
-            }));
-            self.instance_buffer_capacity = quads.len();
-        } else if let Some(instance_buffer) = self.instance_buffer.as_ref() {
-            self.queue.write_buffer(instance_buffer, 0, quads.as_bytes());
+        let frame = &current_frame.output.view;
+
+        // Uniform blocks are written up front into slots reused from frame to frame, so the
+        // draw loop below only has to look their bind groups up.
+        let mut uniform_blocks = Vec::with_capacity(commands.len());
+        let mut next_uniform_block = 0;
+        for command in commands {
+            let mut uniform_block = None;
+            if let DrawCommand::DrawTextureBatch(command) = command {
+                self.ensure_render_pipeline(command.shader, command.blend_mode)?;
+                if let Some(uniforms) = command.uniforms.as_ref() {
+                    self.write_uniform_block(next_uniform_block, uniforms)?;
+                    uniform_block = Some(next_uniform_block);
+                    next_uniform_block += 1;
+                }
+                if let Some(sampler) = command.sampler {
+                    let device = &self.device;
+                    let sampler_resource = self.samplers.entry(sampler).or_insert_with(|| create_sampler(device, sampler));
+                    if let Some(texture) = self.textures.get(&command.texture.id()) {
+                        if sampler != texture.sampler_options {
+                            let texture_layout = &self.texture_layout;
+                            self.sampler_bind_groups.entry((command.texture.id(), sampler)).or_insert_with(|| {
+                                create_texture_bind_group(
+                                    device,
+                                    texture_layout,
+                                    "Sampler Override Bind Group",
+                                    &texture.view,
+                                    sampler_resource,
+                                    &texture.swizzle_buffer,
+                                )
+                            });
+                        }
+                    }
+                }
+            }
+            uniform_blocks.push(uniform_block);
         }
 
-        let instance_buffer = self.instance_buffer.as_ref().unwrap();
+        self.instance_buffer.write(&self.device, &self.queue, quads.as_bytes());
+        self.mesh_vertex_buffer.write(&self.device, &self.queue, vertices.as_bytes());
+        self.mesh_index_buffer.write(&self.device, &self.queue, indices.as_bytes());
 
-        for command in commands {
+        for (command, uniform_block) in commands.iter().zip(uniform_blocks) {
             match command {
                 DrawCommand::DrawTextureBatch(command) => {
-                    let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                        label: Some("Command Encoder"),
-                    });
+                    let instance_buffer = match self.instance_buffer.buffer.as_ref() {
+                        Some(instance_buffer) if !command.range.is_empty() => instance_buffer,
+                        _ => continue,
+                    };
 
-                    let texture = self.textures.get(&command.texture.id()).expect("Texture not found while presenting.");
+                    let texture = self.textures.get(&command.texture.id()).ok_or(Error::TextureNotFound(command.texture))?;
 
-                    let (target, size) = if let Some(target) = command.target {
-                        let texture = self.textures.get(&target.id()).expect("Target not found while presenting.");
-                        (&texture.view, texture.size)
-                    } else {
-                        (view, self.viewport_size)
+                    let target = match self.pass_target(frame, command.target, command.camera, command.scissor)? {
+                        Some(target) => target,
+                        None => continue,
+                    };
+
+                    let texture_bind_group = match command.sampler {
+                        Some(sampler) if sampler != texture.sampler_options => {
+                            &self.sampler_bind_groups[&(command.texture.id(), sampler)]
+                        }
+                        _ => &texture.bind_group,
+                    };
+
+                    let uniform_block_bind_group = match uniform_block {
+                        Some(index) => &self.uniform_blocks[index].bind_group,
+                        None => &self.default_uniform_block.1,
                     };
 
-                    self.queue.write_buffer(&self.uniforms_buffer, 0, Uniforms::new(size).as_bytes());
+                    self.queue.write_buffer(&self.uniforms_buffer, 0, Uniforms::new(target.size, command.camera).as_bytes());
 
+                    let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("Command Encoder"),
+                    });
                     {
-                        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                            label: Some("Texture render pass"),
-                            depth_stencil_attachment: None,
-                            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                                attachment: target,
-                                resolve_target: None,
-                                ops: wgpu::Operations {
-                                    store: true,
-                                    load: wgpu::LoadOp::Load,
-                                },
-                            }],
-                        });
-                        render_pass.set_pipeline(&self.render_pipeline);
-                        render_pass.set_bind_group(0, &texture.bind_group, &[]);
+                        let mut render_pass = target.begin_render_pass(&mut encoder, "Texture render pass");
+                        render_pass.set_pipeline(&self.render_pipelines[&(command.shader, command.blend_mode)]);
+                        render_pass.set_bind_group(0, texture_bind_group, &[]);
                         render_pass.set_bind_group(1, &self.uniforms_bind_group, &[]);
+                        render_pass.set_bind_group(2, uniform_block_bind_group, &[]);
                         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
                         render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
                         render_pass
@@ -129,16 +239,72 @@ impl Backend for BackendWgpu {
                     }
                     self.queue.submit(core::iter::once(encoder.finish()));
                 }
+                DrawCommand::DrawShapes(command) => {
+                    let (vertex_buffer, index_buffer) = match (self.mesh_vertex_buffer.buffer.as_ref(), self.mesh_index_buffer.buffer.as_ref()) {
+                        (Some(vertex_buffer), Some(index_buffer)) if !command.range.is_empty() => (vertex_buffer, index_buffer),
+                        _ => continue,
+                    };
+
+                    let target = match self.pass_target(frame, command.target, command.camera, command.scissor)? {
+                        Some(target) => target,
+                        None => continue,
+                    };
+
+                    self.queue.write_buffer(&self.uniforms_buffer, 0, Uniforms::new(target.size, command.camera).as_bytes());
+
+                    let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("Command Encoder"),
+                    });
+                    {
+                        let mut render_pass = target.begin_render_pass(&mut encoder, "Shapes render pass");
+                        render_pass.set_pipeline(&self.mesh_pipelines[&command.blend_mode]);
+                        render_pass.set_bind_group(0, &self.white_texture.bind_group, &[]);
+                        render_pass.set_bind_group(1, &self.uniforms_bind_group, &[]);
+                        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                        render_pass.draw_indexed(command.range.start as u32..command.range.end as u32, 0, 0..1);
+                    }
+                    self.queue.submit(core::iter::once(encoder.finish()));
+                }
+                DrawCommand::DrawMesh(command) => {
+                    let (vertex_buffer, index_buffer) = match (self.mesh_vertex_buffer.buffer.as_ref(), self.mesh_index_buffer.buffer.as_ref()) {
+                        (Some(vertex_buffer), Some(index_buffer)) if !command.range.is_empty() => (vertex_buffer, index_buffer),
+                        _ => continue,
+                    };
+
+                    let texture = self.textures.get(&command.texture.id()).ok_or(Error::TextureNotFound(command.texture))?;
+
+                    let target = match self.pass_target(frame, command.target, command.camera, command.scissor)? {
+                        Some(target) => target,
+                        None => continue,
+                    };
+
+                    self.queue.write_buffer(&self.uniforms_buffer, 0, Uniforms::new(target.size, command.camera).as_bytes());
+
+                    let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("Command Encoder"),
+                    });
+                    {
+                        let mut render_pass = target.begin_render_pass(&mut encoder, "Mesh render pass");
+                        render_pass.set_pipeline(&self.mesh_pipelines[&command.blend_mode]);
+                        render_pass.set_bind_group(0, &texture.bind_group, &[]);
+                        render_pass.set_bind_group(1, &self.uniforms_bind_group, &[]);
+                        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                        render_pass.draw_indexed(command.range.start as u32..command.range.end as u32, 0, 0..1);
+                    }
+                    self.queue.submit(core::iter::once(encoder.finish()));
+                }
                 DrawCommand::Clear(command) => {
                     let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
                         label: Some("Command Encoder"),
                     });
 
                     let target = if let Some(target) = command.target {
-                        let texture = self.textures.get(&target.id()).expect("Target not found while presenting.");
+                        let texture = self.textures.get(&target.id()).ok_or(Error::TargetNotFound(target))?;
                         &texture.view
                     } else {
-                        view
+                        self.screen.as_ref().map_or(frame, |screen| &screen.view)
                     };
                     encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                         label: Some("Clear render pass"),
@@ -156,32 +322,102 @@ impl Backend for BackendWgpu {
                 }
             }
         }
+
+        if let Some(screen) = self.screen.as_ref() {
+            self.present_screen(screen, frame);
+        }
+
+        Ok(())
     }
 
     fn load_texture(
         &mut self,
         options: TextureLoadOptions,
-    ) -> Result<Texture, String> {
-        let texture_resource = TextureWgpu::new(&self.device, &self.queue, &self.texture_layout, options)?;
+    ) -> Result<Texture> {
+        let texture_resource = TextureWgpu::new(&self.device, &self.queue, &self.texture_layout, &mut self.samplers, options)?;
         let texture = self.next_texture;
         self.next_texture += 1;
         self.textures.insert(texture, texture_resource);
         Ok(Texture::new(texture))
     }
 
+    fn update_texture(&mut self, texture: Texture, rect: Option<Rect>, data: &[u8]) -> Result<()> {
+        let texture_resource = self.textures.get(&texture.id()).ok_or(Error::TextureNotFound(texture))?;
+        let rect = rect.unwrap_or_else(|| texture_resource.size.into());
+        texture_resource.write(&self.queue, rect, data)
+    }
+
+    fn read_texture(&mut self, texture: Texture, rect: Option<Rect>) -> Result<Vec<u8>> {
+        let texture_resource = self.textures.get(&texture.id()).ok_or(Error::TextureNotFound(texture))?;
+        let rect = rect.unwrap_or_else(|| texture_resource.size.into());
+        texture_resource.read(&self.device, &self.queue, rect)
+    }
+
+    fn read_screen(&mut self, rect: Option<Rect>) -> Result<Vec<u8>> {
+        let screen = self.screen.as_ref().ok_or(Error::ScreenCaptureDisabled)?;
+        let rect = rect.unwrap_or_else(|| self.viewport_size.into());
+        screen.read(&self.device, &self.queue, rect)
+    }
+
+    fn set_screen_capture(&mut self, enabled: bool) {
+        if !enabled {
+            self.screen = None;
+        } else if self.screen.is_none() {
+            self.screen = Some(self.create_screen());
+        }
+    }
+
     fn unload_texture(&mut self, texture: Texture) {
         self.textures.remove(&texture.id());
+        self.sampler_bind_groups.retain(|(bind_group_texture, _), _| *bind_group_texture != texture.id());
+        self.uniform_blocks.retain(|slot| !slot.textures.contains(&texture.id()));
+    }
+
+    fn load_shader(&mut self, options: ShaderLoadOptions) -> Result<Shader> {
+        let name = options.name.unwrap_or("Untitled");
+        let vertex = match options.vertex {
+            Some(vertex) => Some(create_shader_module(&self.device, &self.error_scope, vertex, shaderc::ShaderKind::Vertex, name)?),
+            None => None,
+        };
+        let fragment = create_shader_module(&self.device, &self.error_scope, options.fragment, shaderc::ShaderKind::Fragment, name)?;
+        let shader = Shader::new(self.next_shader);
+        self.next_shader += 1;
+        self.shaders.insert(shader.id(), ShaderWgpu { vertex, fragment });
+        // Building a pipeline right away checks the shader against the bind group layouts.
+        if let Err(err) = self.ensure_render_pipeline(Some(shader), BlendMode::default()) {
+            self.unload_shader(shader);
+            return Err(err);
+        }
+        Ok(shader)
+    }
+
+    fn load_builtin_shader(&mut self, shader: BuiltinShader) -> Result<Shader> {
+        let (name, source) = match shader {
+            BuiltinShader::Blur => ("Blur", include_str!("../shaders/effects/blur.frag")),
+            BuiltinShader::Threshold => ("Threshold", include_str!("../shaders/effects/threshold.frag")),
+            BuiltinShader::Intensity => ("Intensity", include_str!("../shaders/effects/intensity.frag")),
+            BuiltinShader::Vignette => ("Vignette", include_str!("../shaders/effects/vignette.frag")),
+            BuiltinShader::ColorGrading => ("Color Grading", include_str!("../shaders/effects/color_grading.frag")),
+            BuiltinShader::Crt => ("CRT", include_str!("../shaders/effects/crt.frag")),
+        };
+        self.load_shader(ShaderLoadOptions::new(ShaderSource::Glsl(source)).with_name(name))
+    }
+
+    fn unload_shader(&mut self, shader: Shader) {
+        self.shaders.remove(&shader.id());
+        self.render_pipelines.retain(|(pipeline_shader, _), _| *pipeline_shader != Some(shader));
     }
 
     fn query_texture(
         &self,
         texture: Texture,
-    ) -> Option<TextureQuery> {
+    ) -> Option<TextureQuery<'_>> {
         let texture = self.textures.get(&texture.id())?;
         Some(TextureQuery {
-            name: texture.name.as_ref().map(|s| s.as_str()),
+            name: texture.name.as_deref(),
             format: texture.format,
             usage: texture.usage,
+            sampler: texture.sampler_options,
             size: texture.size,
         })
     }
@@ -194,7 +430,11 @@ impl Backend for BackendWgpu {
             self.viewport_size.width,
             self.viewport_size.height,
         );
-        self.queue.write_buffer(&self.uniforms_buffer, 0, Uniforms::new(new_size).as_bytes());
+        if self.screen.is_some() {
+            self.screen = Some(self.create_screen());
+        }
+        self.screen_quad = create_screen_quad(&self.device, new_size);
+        self.queue.write_buffer(&self.uniforms_buffer, 0, Uniforms::new(new_size, None).as_bytes());
     }
 
     fn viewport(&self) -> ViewSize {
@@ -203,10 +443,147 @@ impl Backend for BackendWgpu {
 }
 
 impl BackendWgpu {
+    // Only rebuilds the slot's buffer and bind group when the block outgrew it or binds other
+    // textures than last time; otherwise the values are just written into the existing buffer.
+    fn write_uniform_block(&mut self, index: usize, uniforms: &UniformBlock) -> Result<()> {
+        let contents = uniforms.to_std140_bytes();
+        let texture_ids = uniforms.textures().iter().map(Texture::id).collect::<Vec<_>>();
+        match self.uniform_blocks.get(index) {
+            Some(slot) if slot.capacity >= contents.len() && slot.textures == texture_ids => {
+                self.queue.write_buffer(&slot.buffer, 0, &contents);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let textures = uniforms
+            .textures()
+            .iter()
+            .map(|texture| self.textures.get(&texture.id()).ok_or(Error::TextureNotFound(*texture)))
+            .collect::<Result<Vec<_>>>()?;
+        let (buffer, bind_group) = create_uniform_block(
+            &self.device,
+            &self.uniform_block_layout,
+            &contents,
+            &textures,
+            &self.white_texture,
+            &self.samplers,
+        );
+        let slot = UniformBlockSlot {
+            textures: texture_ids,
+            capacity: contents.len(),
+            buffer,
+            bind_group,
+        };
+        if index < self.uniform_blocks.len() {
+            self.uniform_blocks[index] = slot;
+        } else {
+            self.uniform_blocks.push(slot);
+        }
+        Ok(())
+    }
+
+    fn ensure_render_pipeline(&mut self, shader: Option<Shader>, blend_mode: BlendMode) -> Result<()> {
+        if self.render_pipelines.contains_key(&(shader, blend_mode)) {
+            return Ok(());
+        }
+        let (vertex_module, fragment_module) = match shader {
+            Some(shader) => {
+                let shader_resource = self.shaders.get(&shader.id()).ok_or(Error::ShaderNotFound(shader))?;
+                (shader_resource.vertex.as_ref().unwrap_or(&self.vertex_module), &shader_resource.fragment)
+            }
+            None => (&self.vertex_module, &self.fragment_module),
+        };
+        let render_pipeline = self
+            .error_scope
+            .capture(|| {
+                create_render_pipeline(
+                    &self.device,
+                    &self.render_pipeline_layout,
+                    vertex_module,
+                    fragment_module,
+                    &[Vertex::buffer_desc(), quads_buffer_desc()],
+                    blend_mode,
+                )
+            })
+            .map_err(Error::InvalidShader)?;
+        self.render_pipelines.insert((shader, blend_mode), render_pipeline);
+        Ok(())
+    }
+
+    fn create_screen(&mut self) -> TextureWgpu {
+        create_screen(&self.device, &self.queue, &self.texture_layout, &mut self.samplers, self.viewport_size)
+            .expect("Screen texture has no initial data to validate.")
+    }
+
+    // With screen capture enabled, commands targeting the screen are rendered into an offscreen
+    // texture, which is copied to the swap chain afterwards so the last presented frame can be read back.
+    fn present_screen(&self, screen: &TextureWgpu, view: &wgpu::TextureView) {
+        self.queue.write_buffer(&self.uniforms_buffer, 0, Uniforms::new(self.viewport_size, None).as_bytes());
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Command Encoder"),
+        });
+        {
+            let target = PassTarget {
+                attachment: view,
+                size: self.viewport_size,
+                viewport: self.viewport_size.into(),
+                scissor: None,
+            };
+            let mut render_pass = target.begin_render_pass(&mut encoder, "Screen render pass");
+            render_pass.set_pipeline(&self.render_pipelines[&(None, BlendMode::Replace)]);
+            render_pass.set_bind_group(0, &screen.bind_group, &[]);
+            render_pass.set_bind_group(1, &self.uniforms_bind_group, &[]);
+            render_pass.set_bind_group(2, &self.default_uniform_block.1, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.screen_quad.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..QUAD_INDICES.len() as u32, 0, 0..1);
+        }
+        self.queue.submit(core::iter::once(encoder.finish()));
+    }
+
+    fn pass_target<'a>(
+        &'a self,
+        frame: &'a wgpu::TextureView,
+        target: Option<Texture>,
+        camera: Option<Camera2D>,
+        scissor: Option<Rect>,
+    ) -> Result<Option<PassTarget<'a>>> {
+        let (attachment, size) = if let Some(target) = target {
+            let texture = self.textures.get(&target.id()).ok_or(Error::TargetNotFound(target))?;
+            (&texture.view, texture.size)
+        } else {
+            (self.screen.as_ref().map_or(frame, |screen| &screen.view), self.viewport_size)
+        };
+
+        let viewport = camera.map(|camera| camera.viewport_rect(size)).unwrap_or(size.into());
+        if viewport.w <= 0.0 || viewport.h <= 0.0 {
+            return Ok(None);
+        }
+
+        let scissor = match scissor.map(|scissor| scissor.intersection(size.into())) {
+            Some(None) => return Ok(None),
+            Some(scissor) => scissor,
+            None => None,
+        };
+
+        Ok(Some(PassTarget {
+            attachment,
+            size,
+            viewport,
+            scissor,
+        }))
+    }
+
+    /// # Safety
+    ///
+    /// `window` must outlive the returned backend, as its surface is created from the window's raw handle.
     pub async unsafe fn new(
         window: &Window,
         viewport_size: ViewSize,
-    ) -> Result<Self, String> {
+    ) -> Result<Self> {
         let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
 
         let surface = instance.create_surface(window);
@@ -218,7 +595,7 @@ impl BackendWgpu {
                     power_preference: wgpu::PowerPreference::HighPerformance,
                 })
                 .await
-                .ok_or(String::from("Unable to request a suitable wgpu::Adapter."))?;
+                .ok_or(Error::AdapterNotFound)?;
 
             adapter
                 .request_device(
@@ -230,9 +607,15 @@ impl BackendWgpu {
                     None,
                 )
                 .await
-                .map_err(|err| err.to_string())?
+                .map_err(|err| Error::DeviceRequestFailed(Box::new(err)))?
         };
 
+        let error_scope = ErrorScope::default();
+        {
+            let error_scope = error_scope.clone();
+            device.on_uncaptured_error(move |error| error_scope.handle(error));
+        }
+
         let swap_chain = create_swap_chain(&device, &surface, viewport_size.width, viewport_size.height);
 
         let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -252,7 +635,17 @@ impl BackendWgpu {
                     binding: 1,
                     ty: wgpu::BindingType::Sampler {
                         comparison: false,
-                        filtering: false,
+                        filtering: true,
+                    },
+                    count: None,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    ty: wgpu::BindingType::Buffer {
+                        min_binding_size: None,
+                        has_dynamic_offset: false,
+                        ty: wgpu::BufferBindingType::Uniform,
                     },
                     count: None,
                     visibility: wgpu::ShaderStage::FRAGMENT,
@@ -274,7 +667,7 @@ impl BackendWgpu {
             }],
         });
 
-        let uniforms = Uniforms::new(viewport_size);
+        let uniforms = Uniforms::new(viewport_size, None);
 
         let uniforms_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Uniform Buffer"),
@@ -291,60 +684,90 @@ impl BackendWgpu {
             }],
         });
 
-        let render_pipeline = {
-            let render_pipeline_layout =
+        let uniform_block_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Uniform Block Bind Group Layout"),
+            entries: &uniform_block_layout_entries(),
+        });
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[&texture_layout, &uniforms_layout, &uniform_block_layout],
+                push_constant_ranges: &[],
+            });
+
+        let vertex_module = device.create_shader_module(&wgpu::include_spirv!(
+            "../shaders/out/shader.vert.spv"
+        ));
+
+        let fragment_module = device.create_shader_module(&wgpu::include_spirv!(
+            "../shaders/out/shader.frag.spv"
+        ));
+
+        let (render_pipelines, mesh_pipelines) = {
+            let mesh_vertex_module = device.create_shader_module(&wgpu::include_spirv!(
+                "../shaders/out/mesh.vert.spv"
+            ));
+
+            let mesh_pipeline_layout =
                 device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("Render Pipeline Layout"),
+                    label: Some("Mesh Pipeline Layout"),
                     bind_group_layouts: &[&texture_layout, &uniforms_layout],
                     push_constant_ranges: &[],
                 });
 
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
-                layout: Some(&render_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    buffers: &[Vertex::buffer_desc(), quads_buffer_desc()],
-                    module: &device.create_shader_module(&wgpu::include_spirv!(
-                        "../shaders/out/shader.vert.spv"
-                    )),
-                    entry_point: "main",
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &device.create_shader_module(&wgpu::include_spirv!(
-                        "../shaders/out/shader.frag.spv"
-                    )),
-                    entry_point: "main",
-                    targets: &[wgpu::ColorTargetState {
-                        color_blend: wgpu::BlendState {
-                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                        alpha_blend: wgpu::BlendState {
-                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                        format: wgpu::TextureFormat::Bgra8UnormSrgb,
-                        write_mask: wgpu::ColorWrite::ALL,
-                    }],
-                }),
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    alpha_to_coverage_enabled: false,
-                    count: 1,
-                    mask: !0,
-                },
-                primitive: wgpu::PrimitiveState {
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: wgpu::CullMode::Front,
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    strip_index_format: None,
-                },
-            })
+            let create_render_pipelines = |layout, vertex_module, buffers: &[wgpu::VertexBufferLayout]| {
+                BLEND_MODES
+                    .iter()
+                    .map(|&blend_mode| {
+                        let render_pipeline = create_render_pipeline(
+                            &device,
+                            layout,
+                            vertex_module,
+                            &fragment_module,
+                            buffers,
+                            blend_mode,
+                        );
+                        (blend_mode, render_pipeline)
+                    })
+                    .collect::<HashMap<_, _>>()
+            };
+
+            (
+                create_render_pipelines(&render_pipeline_layout, &vertex_module, &[Vertex::buffer_desc(), quads_buffer_desc()])
+                    .into_iter()
+                    .map(|(blend_mode, render_pipeline)| ((None, blend_mode), render_pipeline))
+                    .collect(),
+                create_render_pipelines(&mesh_pipeline_layout, &mesh_vertex_module, &[mesh_buffer_desc()]),
+            )
         };
 
+        let mut samplers = HashMap::new();
+
+        let white_texture = TextureWgpu::new(
+            &device,
+            &queue,
+            &texture_layout,
+            &mut samplers,
+            TextureLoadOptions {
+                name: Some("White"),
+                data: Some(&[u8::MAX; 4]),
+                size: ViewSize::new(1, 1),
+                ..Default::default()
+            },
+        )?;
+
+        let screen_quad = create_screen_quad(&device, viewport_size);
+
+        let default_uniform_block = create_uniform_block(
+            &device,
+            &uniform_block_layout,
+            &UniformBlock::new().to_std140_bytes(),
+            &[],
+            &white_texture,
+            &samplers,
+        );
+
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
             usage: wgpu::BufferUsage::VERTEX,
@@ -365,33 +788,132 @@ impl BackendWgpu {
             swap_chain,
             viewport_size,
             index_buffer,
-            instance_buffer: None,
-            instance_buffer_capacity: 0,
+            instance_buffer: DynamicBuffer::new("Instance Buffer", wgpu::BufferUsage::VERTEX),
+            mesh_vertex_buffer: DynamicBuffer::new("Mesh Vertex Buffer", wgpu::BufferUsage::VERTEX),
+            mesh_index_buffer: DynamicBuffer::new("Mesh Index Buffer", wgpu::BufferUsage::INDEX),
             next_texture: 0,
-            render_pipeline,
+            render_pipeline_layout,
+            vertex_module,
+            fragment_module,
+            render_pipelines,
+            mesh_pipelines,
+            shaders: HashMap::new(),
+            next_shader: 0,
+            error_scope,
             texture_layout,
             textures: HashMap::with_capacity(100),
+            samplers,
+            sampler_bind_groups: HashMap::new(),
+            white_texture,
+            screen: None,
+            screen_quad,
             uniforms_buffer,
             uniforms_bind_group,
             _uniforms_layout: uniforms_layout,
+            uniform_block_layout,
+            default_uniform_block,
+            uniform_blocks: Vec::new(),
             vertex_buffer,
         })
     }
 }
 
+impl ErrorScope {
+    fn handle(&self, error: wgpu::Error) {
+        if let Some(errors) = self.0.lock().unwrap().as_mut() {
+            errors.push(error.to_string());
+            return;
+        }
+        panic!("wgpu error: {}", error);
+    }
+
+    // Runs `f`, returning the errors it raised if any.
+    fn capture<T>(&self, f: impl FnOnce() -> T) -> std::result::Result<T, String> {
+        *self.0.lock().unwrap() = Some(Vec::new());
+        let value = f();
+        let errors = self.0.lock().unwrap().take().unwrap_or_default();
+        if errors.is_empty() {
+            Ok(value)
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+impl DynamicBuffer {
+    fn new(label: &'static str, usage: wgpu::BufferUsage) -> Self {
+        Self {
+            label,
+            usage: usage | wgpu::BufferUsage::COPY_DST,
+            buffer: None,
+            capacity: 0,
+        }
+    }
+
+    fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, contents: &[u8]) {
+        if contents.is_empty() {
+            return;
+        }
+
+        match self.buffer.as_ref() {
+            Some(buffer) if contents.len() <= self.capacity => queue.write_buffer(buffer, 0, contents),
+            _ => {
+                self.buffer = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(self.label),
+                    usage: self.usage,
+                    contents,
+                }));
+                self.capacity = contents.len();
+            }
+        }
+    }
+}
+
+impl<'a> PassTarget<'a> {
+    fn begin_render_pass<'e>(
+        &self,
+        encoder: &'e mut wgpu::CommandEncoder,
+        label: &str,
+    ) -> wgpu::RenderPass<'e>
+    where
+        'a: 'e,
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            depth_stencil_attachment: None,
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: self.attachment,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    store: true,
+                    load: wgpu::LoadOp::Load,
+                },
+            }],
+        });
+        let viewport = self.viewport;
+        render_pass.set_viewport(viewport.x, viewport.y, viewport.w, viewport.h, 0.0, 1.0);
+        if let Some(scissor) = self.scissor {
+            render_pass.set_scissor_rect(scissor.x as u32, scissor.y as u32, scissor.w, scissor.h);
+        }
+        render_pass
+    }
+}
+
 impl TextureWgpu {
     pub fn new(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         bind_group_layout: &wgpu::BindGroupLayout,
+        samplers: &mut HashMap<SamplerOptions, wgpu::Sampler>,
         options: TextureLoadOptions,
-    ) -> Result<Self, String> {
+    ) -> Result<Self> {
         let TextureLoadOptions {
             name,
             data,
             usage,
             format,
             size,
+            sampler: sampler_options,
         } = options;
 
         let texture_size = wgpu::Extent3d {
@@ -400,16 +922,26 @@ impl TextureWgpu {
             depth: 1,
         };
 
+        // Pipelines are built for the swap chain format, so only it can be rendered to.
+        if usage == TextureUsage::RenderTarget && format != TextureFormat::Bgra8UnormSrgb {
+            return Err(Error::UnsupportedFormat(format));
+        }
+
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: name,
             dimension: wgpu::TextureDimension::D2,
             format: match format {
                 TextureFormat::Bgra8UnormSrgb => wgpu::TextureFormat::Bgra8UnormSrgb,
-                _ => panic!("Texture format not supported.")
+                TextureFormat::Bgra8Unorm => wgpu::TextureFormat::Bgra8Unorm,
+                TextureFormat::Rgba8UnormSrgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+                TextureFormat::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+                TextureFormat::R8Unorm => wgpu::TextureFormat::R8Unorm,
+                TextureFormat::Rg8Unorm => wgpu::TextureFormat::Rg8Unorm,
+                TextureFormat::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
             },
             usage: match usage {
-                TextureUsage::Default => wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
-                TextureUsage::RenderTarget => wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+                TextureUsage::Default => wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST | wgpu::TextureUsage::COPY_SRC,
+                TextureUsage::RenderTarget => wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST | wgpu::TextureUsage::COPY_SRC,
             },
             mip_level_count: 1,
             sample_count: 1,
@@ -418,73 +950,163 @@ impl TextureWgpu {
 
         let view = texture.create_view(&Default::default());
 
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        let sampler = samplers.entry(sampler_options).or_insert_with(|| create_sampler(device, sampler_options));
 
-        if let Some(data) = data {
-            queue.write_texture(
-                wgpu::TextureCopyView {
-                    texture: &texture,
-                    mip_level: 0,
-                    origin: wgpu::Origin3d::ZERO,
-                },
-                data,
-                wgpu::TextureDataLayout {
-                    offset: 0,
-                    bytes_per_row: 4 * size.width, // FORMAT!
-                    rows_per_image: size.height,
-                },
-                texture_size,
-            );
-        }
+        let swizzle_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Swizzle Buffer"),
+            usage: wgpu::BufferUsage::UNIFORM,
+            contents: Swizzle::new(format).as_bytes(),
+        });
 
         let name = format!("{} Texture Bind Group", name.unwrap_or("Untitled"));
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some(name.as_str()),
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-        });
+        let bind_group = create_texture_bind_group(device, bind_group_layout, &name, &view, sampler, &swizzle_buffer);
 
-        Ok(Self {
+        let texture = Self {
             name: Some(name),
-            _texture: texture,
+            texture,
             format,
             usage,
             view,
-            _sampler: sampler,
+            sampler_options,
+            swizzle_buffer,
             bind_group,
             size,
-        })
+        };
+
+        if let Some(data) = data {
+            texture.write(queue, size.into(), data)?;
+        }
+
+        Ok(texture)
+    }
+
+    fn write(&self, queue: &wgpu::Queue, rect: Rect, data: &[u8]) -> Result<()> {
+        let bytes_per_row = (self.format.bytes_per_pixel() * rect.w as usize) as u32;
+        let expected = bytes_per_row as usize * rect.h as usize;
+        if data.len() < expected {
+            return Err(Error::InvalidTextureData { expected, actual: data.len() });
+        }
+        if rect.w == 0 || rect.h == 0 {
+            return Ok(());
+        }
+        queue.write_texture(
+            wgpu::TextureCopyView {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: rect.x as u32,
+                    y: rect.y as u32,
+                    z: 0,
+                },
+            },
+            data,
+            wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row,
+                rows_per_image: rect.h,
+            },
+            wgpu::Extent3d {
+                width: rect.w,
+                height: rect.h,
+                depth: 1,
+            },
+        );
+        Ok(())
+    }
+
+    // Copies `rect` into a staging buffer whose rows are padded to the 256 bytes wgpu
+    // requires, then strips the padding once the buffer is mapped.
+    fn read(&self, device: &wgpu::Device, queue: &wgpu::Queue, rect: Rect) -> Result<Vec<u8>> {
+        let row_len = (self.format.bytes_per_pixel() * rect.w as usize) as u32;
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row_len = row_len.div_ceil(alignment) * alignment;
+        if rect.w == 0 || rect.h == 0 {
+            return Ok(Vec::new());
+        }
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: padded_row_len as u64 * rect.h as u64,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Command Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: rect.x as u32,
+                    y: rect.y as u32,
+                    z: 0,
+                },
+            },
+            wgpu::BufferCopyView {
+                buffer: &buffer,
+                layout: wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row: padded_row_len,
+                    rows_per_image: rect.h,
+                },
+            },
+            wgpu::Extent3d {
+                width: rect.w,
+                height: rect.h,
+                depth: 1,
+            },
+        );
+        queue.submit(core::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        futures::executor::block_on(mapping).map_err(Error::backend)?;
+
+        let data = slice
+            .get_mapped_range()
+            .chunks_exact(padded_row_len as usize)
+            .flat_map(|row| &row[..row_len as usize])
+            .copied()
+            .collect();
+        buffer.unmap();
+        Ok(data)
+    }
+}
+
+impl Swizzle {
+    // Single-channel textures are sampled as white with the channel as alpha, so masks
+    // and glyphs can be tinted like any other texture.
+    fn new(format: TextureFormat) -> Self {
+        let (channels, offset) = match format {
+            TextureFormat::R8Unorm => (
+                [[0.0, 0.0, 0.0, 1.0], [0.0; 4], [0.0; 4], [0.0; 4]],
+                [1.0, 1.0, 1.0, 0.0],
+            ),
+            _ => (glam::Mat4::IDENTITY.to_cols_array_2d(), [0.0; 4]),
+        };
+        Self { channels, offset }
     }
 }
 
 impl Uniforms {
-    fn new(size: ViewSize) -> Self {
+    fn new(size: ViewSize, camera: Option<Camera2D>) -> Self {
+        let FRect { w, h, .. } = camera.map(|camera| camera.viewport_rect(size)).unwrap_or(size.into());
         let left = 0.0;
-        let right = size.width as f32;
-        let bottom = size.height as f32;
+        let right = w;
+        let bottom = h;
         let top = 0.0;
         let near = 1.0;
         let far = -1.0;
+        let projection = glam::Mat4::orthographic_rh(left, right, bottom, top, near, far);
+        let view = camera
+            .map(|camera| glam::Mat4::from_cols_array_2d(&camera.view_matrix(size)))
+            .unwrap_or(glam::Mat4::IDENTITY);
         Self {
-            view: glam::Mat4::orthographic_rh(left, right, bottom, top, near, far).to_cols_array_2d()
+            view: (projection * view).to_cols_array_2d()
         }
     }
 }
@@ -514,23 +1136,44 @@ impl AsBytes for Uniforms {
     }
 }
 
+impl AsBytes for Swizzle {
+    fn as_bytes(&self) -> &[u8] {
+        let size = core::mem::size_of::<Self>();
+        unsafe { core::slice::from_raw_parts(self as *const Self as *const u8, size) }
+    }
+}
+
 impl AsBytes for &[Vertex] {
     fn as_bytes(&self) -> &[u8] {
-        let size = core::mem::size_of::<Vertex>() * self.len();
+        let size = core::mem::size_of_val(*self);
         unsafe { core::slice::from_raw_parts(self.as_ptr() as *const u8, size) }
     }
 }
 
 impl AsBytes for &[u16] {
     fn as_bytes(&self) -> &[u8] {
-        let size = core::mem::size_of::<u16>() * self.len();
+        let size = core::mem::size_of_val(*self);
+        unsafe { core::slice::from_raw_parts(self.as_ptr() as *const u8, size) }
+    }
+}
+
+impl AsBytes for &[u32] {
+    fn as_bytes(&self) -> &[u8] {
+        let size = core::mem::size_of_val(*self);
+        unsafe { core::slice::from_raw_parts(self.as_ptr() as *const u8, size) }
+    }
+}
+
+impl AsBytes for &[MeshVertex] {
+    fn as_bytes(&self) -> &[u8] {
+        let size = core::mem::size_of_val(*self);
         unsafe { core::slice::from_raw_parts(self.as_ptr() as *const u8, size) }
     }
 }
 
 impl AsBytes for &[Quad] {
     fn as_bytes(&self) -> &[u8] {
-        let size = core::mem::size_of::<Quad>() * self.len();
+        let size = core::mem::size_of_val(*self);
         unsafe { core::slice::from_raw_parts(self.as_ptr() as *const u8, size) }
     }
 }
@@ -567,10 +1210,216 @@ fn quads_buffer_desc<'a>() -> wgpu::VertexBufferLayout<'a> {
                 shader_location: 5,
                 format: wgpu::VertexFormat::Float4,
             },
+            // color
+            wgpu::VertexAttribute {
+                offset: 5 * core::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                shader_location: 6,
+                format: wgpu::VertexFormat::Float4,
+            },
+        ],
+    }
+}
+
+fn mesh_buffer_desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+    wgpu::VertexBufferLayout {
+        array_stride: core::mem::size_of::<MeshVertex>() as wgpu::BufferAddress,
+        step_mode: wgpu::InputStepMode::Vertex,
+        attributes: &[
+            // position
+            wgpu::VertexAttribute {
+                offset: 0,
+                shader_location: 0,
+                format: wgpu::VertexFormat::Float2,
+            },
+            // uv
+            wgpu::VertexAttribute {
+                offset: core::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                shader_location: 1,
+                format: wgpu::VertexFormat::Float2,
+            },
+            // color
+            wgpu::VertexAttribute {
+                offset: 2 * core::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                shader_location: 2,
+                format: wgpu::VertexFormat::Float4,
+            },
         ],
     }
 }
 
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    vertex_module: &wgpu::ShaderModule,
+    fragment_module: &wgpu::ShaderModule,
+    buffers: &[wgpu::VertexBufferLayout],
+    blend_mode: BlendMode,
+) -> wgpu::RenderPipeline {
+    let (color_blend, alpha_blend) = blend_mode_to_wgpu_blend_states(blend_mode);
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            buffers,
+            module: vertex_module,
+            entry_point: "main",
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: fragment_module,
+            entry_point: "main",
+            targets: &[wgpu::ColorTargetState {
+                color_blend,
+                alpha_blend,
+                format: wgpu::TextureFormat::Bgra8UnormSrgb,
+                write_mask: wgpu::ColorWrite::ALL,
+            }],
+        }),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            alpha_to_coverage_enabled: false,
+            count: 1,
+            mask: !0,
+        },
+        primitive: wgpu::PrimitiveState {
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: wgpu::CullMode::None,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            strip_index_format: None,
+        },
+    })
+}
+
+fn uniform_block_layout_entries() -> Vec<wgpu::BindGroupLayoutEntry> {
+    let mut entries = vec![wgpu::BindGroupLayoutEntry {
+        ty: wgpu::BindingType::Buffer {
+            min_binding_size: None,
+            has_dynamic_offset: false,
+            ty: wgpu::BufferBindingType::Uniform,
+        },
+        binding: 0,
+        count: None,
+        visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+    }];
+    for index in 0..MAX_UNIFORM_TEXTURES as u32 {
+        entries.push(wgpu::BindGroupLayoutEntry {
+            binding: 1 + 2 * index,
+            ty: wgpu::BindingType::Texture {
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+            visibility: wgpu::ShaderStage::FRAGMENT,
+        });
+        entries.push(wgpu::BindGroupLayoutEntry {
+            binding: 2 + 2 * index,
+            ty: wgpu::BindingType::Sampler {
+                comparison: false,
+                filtering: true,
+            },
+            count: None,
+            visibility: wgpu::ShaderStage::FRAGMENT,
+        });
+    }
+    entries
+}
+
+// Unused texture slots are filled with `fallback` so every pipeline sees a complete group.
+fn create_uniform_block(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    contents: &[u8],
+    textures: &[&TextureWgpu],
+    fallback: &TextureWgpu,
+    samplers: &HashMap<SamplerOptions, wgpu::Sampler>,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Uniform Block Buffer"),
+        usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        contents,
+    });
+
+    let mut entries = vec![wgpu::BindGroupEntry {
+        binding: 0,
+        resource: buffer.as_entire_binding(),
+    }];
+    for index in 0..MAX_UNIFORM_TEXTURES {
+        let texture = textures.get(index).copied().unwrap_or(fallback);
+        entries.push(wgpu::BindGroupEntry {
+            binding: 1 + 2 * index as u32,
+            resource: wgpu::BindingResource::TextureView(&texture.view),
+        });
+        entries.push(wgpu::BindGroupEntry {
+            binding: 2 + 2 * index as u32,
+            resource: wgpu::BindingResource::Sampler(&samplers[&texture.sampler_options]),
+        });
+    }
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Uniform Block Bind Group"),
+        layout,
+        entries: &entries,
+    });
+    (buffer, bind_group)
+}
+
+fn create_shader_module(
+    device: &wgpu::Device,
+    error_scope: &ErrorScope,
+    source: ShaderSource,
+    kind: shaderc::ShaderKind,
+    name: &str,
+) -> Result<wgpu::ShaderModule> {
+    let source = match source {
+        ShaderSource::Wgsl(source) => wgpu::ShaderSource::Wgsl(source.into()),
+        ShaderSource::Glsl(source) => {
+            let mut compiler = shaderc::Compiler::new()
+                .ok_or_else(|| Error::InvalidShader("unable to create the GLSL compiler".to_string()))?;
+            let spirv = compiler
+                .compile_into_spirv(source, kind, name, "main", None)
+                .map_err(|err| Error::InvalidShader(err.to_string()))?;
+            wgpu::ShaderSource::SpirV(spirv.as_binary().to_vec().into())
+        }
+    };
+    error_scope
+        .capture(|| {
+            device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some(name),
+                source,
+                flags: wgpu::ShaderFlags::VALIDATION,
+            })
+        })
+        .map_err(Error::InvalidShader)
+}
+
+fn blend_mode_to_wgpu_blend_states(blend_mode: BlendMode) -> (wgpu::BlendState, wgpu::BlendState) {
+    let blend_state = |src_factor, dst_factor| wgpu::BlendState {
+        src_factor,
+        dst_factor,
+        operation: wgpu::BlendOperation::Add,
+    };
+    match blend_mode {
+        BlendMode::Alpha => (
+            blend_state(wgpu::BlendFactor::SrcAlpha, wgpu::BlendFactor::OneMinusSrcAlpha),
+            blend_state(wgpu::BlendFactor::SrcAlpha, wgpu::BlendFactor::OneMinusSrcAlpha),
+        ),
+        BlendMode::Additive => (
+            blend_state(wgpu::BlendFactor::SrcAlpha, wgpu::BlendFactor::One),
+            blend_state(wgpu::BlendFactor::Zero, wgpu::BlendFactor::One),
+        ),
+        BlendMode::Multiply => (
+            blend_state(wgpu::BlendFactor::DstColor, wgpu::BlendFactor::OneMinusSrcAlpha),
+            blend_state(wgpu::BlendFactor::Zero, wgpu::BlendFactor::One),
+        ),
+        BlendMode::Premultiplied => (
+            blend_state(wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha),
+            blend_state(wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha),
+        ),
+        BlendMode::Replace => (wgpu::BlendState::REPLACE, wgpu::BlendState::REPLACE),
+    }
+}
+
 fn color_to_wgpu_color(color: Color) -> wgpu::Color {
     wgpu::Color {
         r: color.r as f64 / u8::MAX as f64,
@@ -587,7 +1436,7 @@ fn create_swap_chain(
     height: u32,
 ) -> wgpu::SwapChain {
     device.create_swap_chain(
-        &surface,
+        surface,
         &wgpu::SwapChainDescriptor {
             present_mode: wgpu::PresentMode::Fifo,
             usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
@@ -597,3 +1446,82 @@ fn create_swap_chain(
         },
     )
 }
+
+fn create_screen(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture_layout: &wgpu::BindGroupLayout,
+    samplers: &mut HashMap<SamplerOptions, wgpu::Sampler>,
+    size: ViewSize,
+) -> Result<TextureWgpu> {
+    TextureWgpu::new(
+        device,
+        queue,
+        texture_layout,
+        samplers,
+        TextureLoadOptions {
+            name: Some("Screen"),
+            size,
+            usage: TextureUsage::RenderTarget,
+            ..Default::default()
+        },
+    )
+}
+
+fn create_screen_quad(device: &wgpu::Device, size: ViewSize) -> wgpu::Buffer {
+    let quad = Quad::new(size, size, Transform2D::IDENTITY, DrawOptions::default());
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Screen Quad Buffer"),
+        usage: wgpu::BufferUsage::VERTEX,
+        contents: core::slice::from_ref(&quad).as_bytes(),
+    })
+}
+
+fn create_sampler(device: &wgpu::Device, options: SamplerOptions) -> wgpu::Sampler {
+    let address_mode = |address_mode| match address_mode {
+        AddressMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        AddressMode::Repeat => wgpu::AddressMode::Repeat,
+        AddressMode::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+    };
+    let filter_mode = |filter_mode| match filter_mode {
+        FilterMode::Nearest => wgpu::FilterMode::Nearest,
+        FilterMode::Linear => wgpu::FilterMode::Linear,
+    };
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        address_mode_u: address_mode(options.address_mode_u),
+        address_mode_v: address_mode(options.address_mode_v),
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: filter_mode(options.mag_filter),
+        min_filter: filter_mode(options.min_filter),
+        mipmap_filter: filter_mode(options.mipmap_filter),
+        ..Default::default()
+    })
+}
+
+fn create_texture_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    label: &str,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+    swizzle_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some(label),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: swizzle_buffer.as_entire_binding(),
+            },
+        ],
+    })
+}
@@ -0,0 +1,289 @@
+use crate::{
+    commons::{FRect, Rect, ViewSize},
+    texture::Texture,
+};
+
+#[derive(Clone, Debug)]
+pub struct SpriteSheet {
+    pub texture: Texture,
+    frames: Vec<Rect>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AnimationMode {
+    Loop,
+    PingPong,
+    Once,
+}
+
+#[derive(Clone, Debug)]
+pub struct Animation {
+    frames: Vec<usize>,
+    durations: Vec<f32>,
+    mode: AnimationMode,
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationPlayer {
+    animation: Animation,
+    time: f32,
+    speed: f32,
+    playing: bool,
+}
+
+impl SpriteSheet {
+    pub fn new(texture: Texture, frames: Vec<Rect>) -> Self {
+        Self { texture, frames }
+    }
+
+    pub fn from_grid(texture: Texture, frame_size: ViewSize, columns: u32, rows: u32) -> Self {
+        let ViewSize { width, height } = frame_size;
+        let frames = (0..rows)
+            .flat_map(|row| {
+                (0..columns).map(move |column| {
+                    Rect::new((column * width) as i32, (row * height) as i32, width, height)
+                })
+            })
+            .collect();
+        Self { texture, frames }
+    }
+
+    pub fn frame(&self, index: usize) -> Option<Rect> {
+        self.frames.get(index).copied()
+    }
+
+    pub fn frames(&self) -> &[Rect] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+impl Animation {
+    pub fn new(frames: Vec<usize>, frame_duration: f32, mode: AnimationMode) -> Self {
+        let durations = vec![frame_duration; frames.len()];
+        Self {
+            frames,
+            durations,
+            mode,
+        }
+    }
+
+    pub fn with_durations(frames: Vec<(usize, f32)>, mode: AnimationMode) -> Self {
+        let (frames, durations) = frames.into_iter().unzip();
+        Self {
+            frames,
+            durations,
+            mode,
+        }
+    }
+
+    pub fn mode(&self) -> AnimationMode {
+        self.mode
+    }
+
+    pub fn frames(&self) -> &[usize] {
+        &self.frames
+    }
+
+    pub fn durations(&self) -> &[f32] {
+        &self.durations
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.durations.iter().sum()
+    }
+
+    // Returns the position within `frames` shown after `time` seconds, and whether
+    // a `Once` animation has run to its end.
+    fn frame_at(&self, time: f32) -> (usize, bool) {
+        let len = self.frames.len();
+        if len == 0 {
+            return (0, true);
+        }
+
+        // PingPong plays the frames forward, then back without repeating either end.
+        let backward = match self.mode {
+            AnimationMode::PingPong if len > 2 => 1..len - 1,
+            _ => 0..0,
+        };
+        let sequence = || (0..len).chain(backward.clone().rev());
+        let period: f32 = sequence().map(|index| self.durations[index]).sum();
+        if period <= 0.0 {
+            return (0, self.mode == AnimationMode::Once);
+        }
+
+        let mut time = match self.mode {
+            AnimationMode::Once if time >= period => return (len - 1, true),
+            AnimationMode::Once => time,
+            AnimationMode::Loop | AnimationMode::PingPong => time.rem_euclid(period),
+        };
+
+        let mut last = 0;
+        for index in sequence() {
+            if time < self.durations[index] {
+                return (index, false);
+            }
+            time -= self.durations[index];
+            last = index;
+        }
+        (last, false)
+    }
+}
+
+impl AnimationPlayer {
+    pub fn new(animation: Animation) -> Self {
+        Self {
+            animation,
+            time: 0.0,
+            speed: 1.0,
+            playing: true,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        if self.playing {
+            self.time += delta_time * self.speed;
+        }
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn reset(&mut self) {
+        self.time = 0.0;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.animation.frame_at(self.time).1
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    pub fn set_animation(&mut self, animation: Animation) {
+        self.animation = animation;
+        self.time = 0.0;
+    }
+
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    pub fn current_frame(&self) -> Option<usize> {
+        let (index, _) = self.animation.frame_at(self.time);
+        self.animation.frames.get(index).copied()
+    }
+
+    pub fn src_rect(&self, sheet: &SpriteSheet) -> Option<FRect> {
+        self.current_frame()
+            .and_then(|frame| sheet.frame(frame))
+            .map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames_at(animation: Animation, times: &[f32]) -> Vec<Option<usize>> {
+        times
+            .iter()
+            .map(|&time| {
+                let mut player = AnimationPlayer::new(animation.clone());
+                player.update(time);
+                player.current_frame()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loop_wraps_around() {
+        let animation = Animation::new(vec![10, 11, 12], 1.0, AnimationMode::Loop);
+        assert_eq!(
+            frames_at(animation, &[0.0, 0.5, 1.0, 2.5, 3.0, 4.5, -0.5]),
+            vec![Some(10), Some(10), Some(11), Some(12), Some(10), Some(11), Some(12)]
+        );
+    }
+
+    #[test]
+    fn loop_respects_frame_durations() {
+        let animation = Animation::with_durations(vec![(0, 1.0), (1, 3.0), (2, 0.5)], AnimationMode::Loop);
+        assert_eq!(
+            frames_at(animation, &[0.5, 1.0, 3.9, 4.0, 4.4, 4.5]),
+            vec![Some(0), Some(1), Some(1), Some(2), Some(2), Some(0)]
+        );
+    }
+
+    #[test]
+    fn ping_pong_does_not_repeat_either_end() {
+        let animation = Animation::new(vec![0, 1, 2, 3], 1.0, AnimationMode::PingPong);
+        let times: Vec<f32> = (0..8).map(|time| time as f32 + 0.5).collect();
+        assert_eq!(
+            frames_at(animation, &times),
+            vec![Some(0), Some(1), Some(2), Some(3), Some(2), Some(1), Some(0), Some(1)]
+        );
+    }
+
+    #[test]
+    fn ping_pong_with_two_frames_alternates() {
+        let animation = Animation::new(vec![0, 1], 1.0, AnimationMode::PingPong);
+        assert_eq!(
+            frames_at(animation, &[0.5, 1.5, 2.5, 3.5]),
+            vec![Some(0), Some(1), Some(0), Some(1)]
+        );
+    }
+
+    #[test]
+    fn once_stops_on_the_last_frame() {
+        let animation = Animation::new(vec![0, 1, 2], 1.0, AnimationMode::Once);
+        assert_eq!(
+            frames_at(animation, &[0.5, 1.5, 2.5, 3.0, 10.0]),
+            vec![Some(0), Some(1), Some(2), Some(2), Some(2)]
+        );
+    }
+
+    #[test]
+    fn only_once_animations_finish() {
+        let mut once = AnimationPlayer::new(Animation::new(vec![0, 1], 1.0, AnimationMode::Once));
+        let mut looping = AnimationPlayer::new(Animation::new(vec![0, 1], 1.0, AnimationMode::Loop));
+        let mut ping_pong = AnimationPlayer::new(Animation::new(vec![0, 1], 1.0, AnimationMode::PingPong));
+
+        once.update(1.9);
+        assert!(!once.is_finished());
+        for player in [&mut once, &mut looping, &mut ping_pong] {
+            player.update(10.0);
+        }
+        assert!(once.is_finished());
+        assert!(!looping.is_finished());
+        assert!(!ping_pong.is_finished());
+    }
+
+    #[test]
+    fn empty_and_zero_length_animations() {
+        let empty = AnimationPlayer::new(Animation::new(Vec::new(), 1.0, AnimationMode::Loop));
+        assert_eq!(empty.current_frame(), None);
+        assert!(empty.is_finished());
+
+        let mut instant = AnimationPlayer::new(Animation::new(vec![4, 5], 0.0, AnimationMode::Loop));
+        instant.update(1.0);
+        assert_eq!(instant.current_frame(), Some(4));
+        assert!(!instant.is_finished());
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{
+    backend::Backend,
+    commons::{FRect, Rect, ViewSize},
+    error::{Error, Result},
+    graphics::Graphics,
+    texture::{Texture, TextureFormat, TextureLoadOptions},
+};
+
+#[derive(Copy, Clone, Debug)]
+pub struct AtlasImage<'a> {
+    pub data: &'a [u8],
+    pub size: ViewSize,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct AtlasOptions<'a> {
+    pub name: Option<&'a str>,
+    pub page_size: ViewSize,
+    pub padding: u32,
+    pub format: TextureFormat,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SubTexture {
+    pub texture: Texture,
+    pub rect: Rect,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TextureAtlas {
+    pub pages: Vec<Texture>,
+    pub sub_textures: Vec<SubTexture>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShelfPacker {
+    size: ViewSize,
+    padding: u32,
+    shelves: Vec<Shelf>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    width: u32,
+}
+
+impl<'a> AtlasImage<'a> {
+    pub fn new(data: &'a [u8], size: ViewSize) -> Self {
+        Self { data, size }
+    }
+}
+
+impl Default for AtlasOptions<'_> {
+    fn default() -> Self {
+        Self {
+            name: None,
+            page_size: ViewSize::new(1024, 1024),
+            padding: 1,
+            format: TextureFormat::default(),
+        }
+    }
+}
+
+impl SubTexture {
+    #[inline(always)]
+    pub fn src_rect(&self) -> FRect {
+        self.rect.into()
+    }
+}
+
+impl ShelfPacker {
+    pub fn new(size: ViewSize, padding: u32) -> Self {
+        Self {
+            size,
+            padding,
+            shelves: Vec::new(),
+        }
+    }
+
+    pub fn size(&self) -> ViewSize {
+        self.size
+    }
+
+    pub fn fits(&self, size: ViewSize) -> bool {
+        size.width + 2 * self.padding <= self.size.width
+            && size.height + 2 * self.padding <= self.size.height
+    }
+
+    pub fn insert(&mut self, size: ViewSize) -> Option<Rect> {
+        let width = size.width + self.padding;
+        let height = size.height + self.padding;
+        let max_width = self.size.width.saturating_sub(self.padding);
+
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height && shelf.width + width <= max_width)
+            .min_by_key(|shelf| shelf.height - height);
+
+        let shelf = match shelf {
+            Some(shelf) => shelf,
+            None => {
+                let y = self
+                    .shelves
+                    .last()
+                    .map(|shelf| shelf.y + shelf.height)
+                    .unwrap_or(self.padding);
+                if y + height > self.size.height || width > max_width {
+                    return None;
+                }
+                self.shelves.push(Shelf { y, height, width: 0 });
+                self.shelves.last_mut().unwrap()
+            }
+        };
+
+        let x = self.padding + shelf.width;
+        shelf.width += width;
+        Some(Rect::new(x as i32, shelf.y as i32, size.width, size.height))
+    }
+
+    pub fn clear(&mut self) {
+        self.shelves.clear();
+    }
+}
+
+pub fn pack_atlas<B>(
+    graphics: &mut Graphics<B>,
+    images: &[AtlasImage],
+    options: AtlasOptions,
+) -> Result<TextureAtlas>
+where
+    B: Backend,
+{
+    let AtlasOptions {
+        name,
+        page_size,
+        padding,
+        format,
+    } = options;
+
+    let bytes_per_pixel = format.bytes_per_pixel();
+    let mut order: Vec<usize> = (0..images.len()).collect();
+    order.sort_by_key(|&index| std::cmp::Reverse(images[index].size.height));
+
+    let mut packers: Vec<ShelfPacker> = Vec::new();
+    let mut placements = vec![(0, Rect::default()); images.len()];
+    for index in order {
+        let AtlasImage { data, size } = images[index];
+        let expected = bytes_per_pixel * size.width as usize * size.height as usize;
+        if data.len() < expected {
+            return Err(Error::InvalidTextureData {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        let placement = packers
+            .iter_mut()
+            .enumerate()
+            .find_map(|(page, packer)| packer.insert(size).map(|rect| (page, rect)));
+        placements[index] = match placement {
+            Some(placement) => placement,
+            None => {
+                // Anything that does not fit in an empty page never will.
+                let mut packer = ShelfPacker::new(page_size, padding);
+                let rect = packer.insert(size).ok_or(Error::AtlasImageTooLarge(size))?;
+                packers.push(packer);
+                (packers.len() - 1, rect)
+            }
+        };
+    }
+
+    let stride = bytes_per_pixel * page_size.width as usize;
+    let mut pages_data = vec![vec![0u8; stride * page_size.height as usize]; packers.len()];
+    for (image, &(page, rect)) in images.iter().zip(placements.iter()) {
+        let row_length = bytes_per_pixel * rect.w as usize;
+        for row in 0..rect.h as usize {
+            let src = row * row_length;
+            let dst = (rect.y as usize + row) * stride + rect.x as usize * bytes_per_pixel;
+            pages_data[page][dst..dst + row_length].copy_from_slice(&image.data[src..src + row_length]);
+        }
+    }
+
+    let mut pages = Vec::with_capacity(pages_data.len());
+    for data in pages_data.iter() {
+        let page = graphics.load_texture(TextureLoadOptions {
+            name,
+            data: Some(data),
+            size: page_size,
+            format,
+            ..Default::default()
+        });
+        match page {
+            Ok(page) => pages.push(page),
+            Err(err) => {
+                for page in pages {
+                    graphics.unload_texture(page);
+                }
+                return Err(err);
+            }
+        }
+    }
+
+    let sub_textures = placements
+        .into_iter()
+        .map(|(page, rect)| SubTexture {
+            texture: pages[page],
+            rect,
+        })
+        .collect();
+
+    Ok(TextureAtlas {
+        pages,
+        sub_textures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padding_surrounds_every_rect() {
+        let mut packer = ShelfPacker::new(ViewSize::new(16, 16), 1);
+        assert_eq!(packer.insert(ViewSize::new(4, 4)), Some(Rect::new(1, 1, 4, 4)));
+        assert_eq!(packer.insert(ViewSize::new(4, 4)), Some(Rect::new(6, 1, 4, 4)));
+        assert_eq!(packer.insert(ViewSize::new(4, 4)), Some(Rect::new(11, 1, 4, 4)));
+        assert_eq!(packer.insert(ViewSize::new(4, 4)), Some(Rect::new(1, 6, 4, 4)));
+    }
+
+    #[test]
+    fn full_shelves_wrap_to_a_new_one() {
+        let mut packer = ShelfPacker::new(ViewSize::new(16, 16), 0);
+        assert_eq!(packer.insert(ViewSize::new(8, 4)), Some(Rect::new(0, 0, 8, 4)));
+        assert_eq!(packer.insert(ViewSize::new(8, 4)), Some(Rect::new(8, 0, 8, 4)));
+        assert_eq!(packer.insert(ViewSize::new(8, 4)), Some(Rect::new(0, 4, 8, 4)));
+    }
+
+    #[test]
+    fn picks_the_shelf_that_wastes_the_least_height() {
+        let mut packer = ShelfPacker::new(ViewSize::new(16, 32), 0);
+        assert_eq!(packer.insert(ViewSize::new(8, 8)), Some(Rect::new(0, 0, 8, 8)));
+        assert_eq!(packer.insert(ViewSize::new(12, 4)), Some(Rect::new(0, 8, 12, 4)));
+        assert_eq!(packer.insert(ViewSize::new(4, 4)), Some(Rect::new(12, 8, 4, 4)));
+        assert_eq!(packer.insert(ViewSize::new(4, 5)), Some(Rect::new(8, 0, 4, 5)));
+    }
+
+    #[test]
+    fn returns_none_once_the_page_is_full() {
+        let mut packer = ShelfPacker::new(ViewSize::new(8, 8), 0);
+        assert!(packer.insert(ViewSize::new(8, 6)).is_some());
+        assert_eq!(packer.insert(ViewSize::new(8, 4)), None);
+
+        packer.clear();
+        assert_eq!(packer.insert(ViewSize::new(8, 4)), Some(Rect::new(0, 0, 8, 4)));
+    }
+
+    #[test]
+    fn fits_accounts_for_padding() {
+        let packer = ShelfPacker::new(ViewSize::new(8, 8), 1);
+        assert!(packer.fits(ViewSize::new(6, 6)));
+        assert!(!packer.fits(ViewSize::new(7, 6)));
+        assert!(!packer.fits(ViewSize::new(6, 7)));
+    }
+}
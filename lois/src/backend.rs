@@ -1,10 +1,23 @@
-use crate::{commons::ViewSize, graphics::DrawCommand, quad::Quad, texture::{Texture, TextureLoadOptions, TextureQuery}};
+use crate::{commons::{Rect, ViewSize}, error::Result, graphics::DrawCommand, mesh::MeshVertex, postprocess::BuiltinShader, quad::Quad, shader::{Shader, ShaderLoadOptions}, texture::{Texture, TextureLoadOptions, TextureQuery}};
 
 pub trait Backend {
-    fn present(&mut self, commands: &[DrawCommand], quads: &[Quad]);
-    fn load_texture(&mut self, options: TextureLoadOptions) -> Result<Texture, String>;
+    fn present(
+        &mut self,
+        commands: &[DrawCommand],
+        quads: &[Quad],
+        vertices: &[MeshVertex],
+        indices: &[u32],
+    ) -> Result<()>;
+    fn load_texture(&mut self, options: TextureLoadOptions) -> Result<Texture>;
+    fn update_texture(&mut self, texture: Texture, rect: Option<Rect>, data: &[u8]) -> Result<()>;
+    fn read_texture(&mut self, texture: Texture, rect: Option<Rect>) -> Result<Vec<u8>>;
+    fn read_screen(&mut self, rect: Option<Rect>) -> Result<Vec<u8>>;
+    fn set_screen_capture(&mut self, enabled: bool);
     fn unload_texture(&mut self, texture: Texture);
-    fn query_texture(&self, texture: Texture) -> Option<TextureQuery>;
+    fn load_shader(&mut self, options: ShaderLoadOptions) -> Result<Shader>;
+    fn load_builtin_shader(&mut self, shader: BuiltinShader) -> Result<Shader>;
+    fn unload_shader(&mut self, shader: Shader);
+    fn query_texture(&self, texture: Texture) -> Option<TextureQuery<'_>>;
     fn resize_viewport(&mut self, new_size: ViewSize);
     fn viewport(&self) -> ViewSize;
 }
@@ -1,50 +1,404 @@
-use crate::{commons::ViewSize, graphics::{DrawOptions, DrawTextureBatchCommand}, quad::Quad, texture::Texture};
+use crate::{commons::{Color, FRect, Insets, ViewSize}, graphics::{DrawOptions, DrawTextureBatchCommand}, quad::Quad, shader::{Shader, UniformBlock}, texture::{SamplerOptions, Texture}, transform::Transform2D};
 
 pub struct TextureBatch<'a> {
     quads: &'a mut Vec<Quad>,
+    layers: &'a mut Vec<i32>,
     command: &'a mut DrawTextureBatchCommand,
     target_size: ViewSize,
     texture_size: ViewSize,
+    transform: Transform2D,
 }
 
 #[derive(Copy, Clone, Debug)]
-pub struct TextureBatchOptions {
+pub struct TextureBatchOptions<'a> {
     pub texture: Texture,
     pub target: Option<Texture>,
+    pub blend_mode: BlendMode,
+    pub shader: Option<Shader>,
+    pub uniforms: Option<&'a UniformBlock>,
+    pub sampler: Option<SamplerOptions>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    #[default]
+    Alpha,
+    Additive,
+    // Multiply and Premultiplied expect textures with premultiplied alpha. Tint colors are
+    // premultiplied when drawn, so their alpha still fades the whole quad.
+    Multiply,
+    Premultiplied,
+    Replace,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SliceMode {
+    #[default]
+    Stretch,
+    Tile,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct NineSliceOptions {
+    pub src_rect: Option<FRect>,
+    pub dest_rect: FRect,
+    pub insets: Insets,
+    pub edge_mode: SliceMode,
+    pub center_mode: SliceMode,
+    pub color: Color,
+    pub layer: i32,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Span {
+    src: f32,
+    src_len: f32,
+    dest: f32,
+    dest_len: f32,
 }
 
 impl<'a> TextureBatch<'a> {
     pub(crate) fn new(
         target_size: ViewSize,
         texture_size: ViewSize,
+        transform: Transform2D,
         quads: &'a mut Vec<Quad>,
+        layers: &'a mut Vec<i32>,
         command: &'a mut DrawTextureBatchCommand,
     ) -> Self {
         Self {
             quads,
+            layers,
             command,
             target_size,
             texture_size,
+            transform,
         }
     }
 
-    pub fn draw(mut self, options: DrawOptions) -> Self {
-        let quad = Quad::new(
-            self.target_size,
-            self.texture_size,
-            options.src_rect,
-            options.dest_rect,
-            options.rotation_center,
-            options.rotation_angle,
-        );
+    pub fn draw(self, options: DrawOptions) -> Self {
+        let quad = Quad::new(self.target_size, self.texture_size, self.transform, options)
+            .with_blend_mode(self.command.blend_mode);
         self.quads.push(quad);
+        self.layers.push(options.layer);
         self.command.range.end += 1;
         self
     }
+
+    pub fn draw_nine_slice(self, options: NineSliceOptions) -> Self {
+        let NineSliceOptions {
+            src_rect,
+            dest_rect,
+            insets,
+            edge_mode,
+            center_mode,
+            color,
+            layer,
+        } = options;
+
+        let src_rect = src_rect.unwrap_or_else(|| self.texture_size.into());
+        let columns = slice_axis(
+            src_rect.x,
+            src_rect.w,
+            insets.left as f32,
+            insets.right as f32,
+            dest_rect.x,
+            dest_rect.w,
+        );
+        let rows = slice_axis(
+            src_rect.y,
+            src_rect.h,
+            insets.top as f32,
+            insets.bottom as f32,
+            dest_rect.y,
+            dest_rect.h,
+        );
+
+        let mut batch = self;
+        for (row_index, row) in rows.iter().enumerate() {
+            for (column_index, column) in columns.iter().enumerate() {
+                let mode = match (column_index, row_index) {
+                    (1, 1) => center_mode,
+                    (1, _) | (_, 1) => edge_mode,
+                    _ => SliceMode::Stretch,
+                };
+                let tile_x = mode == SliceMode::Tile && column_index == 1;
+                let tile_y = mode == SliceMode::Tile && row_index == 1;
+                for x in tile_spans(*column, tile_x) {
+                    for y in tile_spans(*row, tile_y) {
+                        batch = batch.draw(DrawOptions {
+                            src_rect: Some(FRect::new(x.src, y.src, x.src_len, y.src_len)),
+                            dest_rect: Some(FRect::new(x.dest, y.dest, x.dest_len, y.dest_len)),
+                            color,
+                            layer,
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+        }
+        batch
+    }
 }
 
-impl TextureBatchOptions {
+fn slice_axis(
+    src: f32,
+    src_len: f32,
+    border_start: f32,
+    border_end: f32,
+    dest: f32,
+    dest_len: f32,
+) -> [Span; 3] {
+    let borders = border_start + border_end;
+    let scale = if borders > dest_len && borders > 0.0 {
+        dest_len / borders
+    } else {
+        1.0
+    };
+    let dest_start = border_start * scale;
+    let dest_end = border_end * scale;
+    [
+        Span {
+            src,
+            src_len: border_start,
+            dest,
+            dest_len: dest_start,
+        },
+        Span {
+            src: src + border_start,
+            src_len: (src_len - borders).max(0.0),
+            dest: dest + dest_start,
+            dest_len: (dest_len - dest_start - dest_end).max(0.0),
+        },
+        Span {
+            src: src + src_len - border_end,
+            src_len: border_end,
+            dest: dest + dest_len - dest_end,
+            dest_len: dest_end,
+        },
+    ]
+}
+
+fn tile_spans(span: Span, tile: bool) -> Vec<Span> {
+    if span.src_len <= 0.0 || span.dest_len <= 0.0 {
+        return Vec::new();
+    }
+    if !tile {
+        return vec![span];
+    }
+    let mut spans = Vec::with_capacity((span.dest_len / span.src_len).ceil() as usize);
+    let mut offset = 0.0;
+    while offset < span.dest_len {
+        let len = span.src_len.min(span.dest_len - offset);
+        spans.push(Span {
+            src: span.src,
+            src_len: len,
+            dest: span.dest + offset,
+            dest_len: len,
+        });
+        offset += len;
+    }
+    spans
+}
+
+impl Default for NineSliceOptions {
+    fn default() -> Self {
+        Self {
+            src_rect: None,
+            dest_rect: FRect::default(),
+            insets: Insets::default(),
+            edge_mode: SliceMode::default(),
+            center_mode: SliceMode::default(),
+            color: Color::WHITE,
+            layer: 0,
+        }
+    }
+}
+
+impl BlendMode {
+    pub(crate) fn tint(self, color: [f32; 4]) -> [f32; 4] {
+        let [r, g, b, a] = color;
+        match self {
+            BlendMode::Multiply | BlendMode::Premultiplied => [r * a, g * a, b * a, a],
+            _ => color,
+        }
+    }
+}
+
+impl<'a> TextureBatchOptions<'a> {
     pub fn new(texture: Texture, target: Option<Texture>) -> Self {
-        Self { texture, target }
+        Self {
+            texture,
+            target,
+            blend_mode: BlendMode::default(),
+            shader: None,
+            uniforms: None,
+            sampler: None,
+        }
+    }
+
+    pub fn with_blend_mode(self, blend_mode: BlendMode) -> Self {
+        Self { blend_mode, ..self }
+    }
+
+    pub fn with_shader(self, shader: Shader) -> Self {
+        Self {
+            shader: Some(shader),
+            ..self
+        }
+    }
+
+    pub fn with_uniforms(self, uniforms: &'a UniformBlock) -> Self {
+        Self {
+            uniforms: Some(uniforms),
+            ..self
+        }
+    }
+
+    pub fn with_sampler(self, sampler: SamplerOptions) -> Self {
+        Self {
+            sampler: Some(sampler),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXTURE_SIZE: f32 = 16.0;
+
+    // Draws a nine-slice of a 16x16 texture and returns the dest and src rect of every
+    // emitted quad, in pixels.
+    fn nine_slice(dest_rect: FRect, insets: Insets, edge_mode: SliceMode, center_mode: SliceMode) -> Vec<(FRect, FRect)> {
+        let mut quads = Vec::new();
+        let mut layers = Vec::new();
+        let mut command = DrawTextureBatchCommand {
+            texture: Texture::new(0),
+            target: None,
+            blend_mode: BlendMode::default(),
+            shader: None,
+            uniforms: None,
+            sampler: None,
+            camera: None,
+            scissor: None,
+            range: 0..0,
+        };
+        TextureBatch::new(
+            ViewSize::new(64, 64),
+            ViewSize::new(TEXTURE_SIZE as u32, TEXTURE_SIZE as u32),
+            Transform2D::IDENTITY,
+            &mut quads,
+            &mut layers,
+            &mut command,
+        )
+        .draw_nine_slice(NineSliceOptions {
+            dest_rect,
+            insets,
+            edge_mode,
+            center_mode,
+            ..Default::default()
+        });
+        assert_eq!(command.range, 0..quads.len());
+
+        quads
+            .iter()
+            .map(|quad| {
+                let [x, y, w, h] = quad.src_rect();
+                (
+                    quad.bounds(),
+                    FRect::new(x * TEXTURE_SIZE, y * TEXTURE_SIZE, w * TEXTURE_SIZE, h * TEXTURE_SIZE),
+                )
+            })
+            .collect()
+    }
+
+    // Every combination of a row and a column span, rows first, as (dest, src) pairs.
+    fn grid(rows: &[(f32, f32, f32, f32)], columns: &[(f32, f32, f32, f32)]) -> Vec<(FRect, FRect)> {
+        let mut rects = Vec::new();
+        for &(dest_y, dest_h, src_y, src_h) in rows {
+            for &(dest_x, dest_w, src_x, src_w) in columns {
+                rects.push((FRect::new(dest_x, dest_y, dest_w, dest_h), FRect::new(src_x, src_y, src_w, src_h)));
+            }
+        }
+        rects
+    }
+
+    #[test]
+    fn stretch_scales_edges_and_center() {
+        let rects = nine_slice(FRect::new(10.0, 20.0, 32.0, 24.0), Insets::uniform(4), SliceMode::Stretch, SliceMode::Stretch);
+
+        let rows = [(20.0, 4.0, 0.0, 4.0), (24.0, 16.0, 4.0, 8.0), (40.0, 4.0, 12.0, 4.0)];
+        let columns = [(10.0, 4.0, 0.0, 4.0), (14.0, 24.0, 4.0, 8.0), (38.0, 4.0, 12.0, 4.0)];
+        assert_eq!(rects, grid(&rows, &columns));
+    }
+
+    #[test]
+    fn tile_repeats_the_center_and_crops_the_remainder() {
+        let rects = nine_slice(FRect::new(0.0, 0.0, 28.0, 20.0), Insets::uniform(4), SliceMode::Stretch, SliceMode::Tile);
+
+        let top = [(0.0, 4.0, 0.0, 4.0)];
+        let middle = [(4.0, 8.0, 4.0, 8.0), (12.0, 4.0, 4.0, 4.0)];
+        let bottom = [(16.0, 4.0, 12.0, 4.0)];
+        let left = [(0.0, 4.0, 0.0, 4.0)];
+        let center = [(4.0, 8.0, 4.0, 8.0), (12.0, 8.0, 4.0, 8.0), (20.0, 4.0, 4.0, 4.0)];
+        let stretched = [(4.0, 20.0, 4.0, 8.0)];
+        let right = [(24.0, 4.0, 12.0, 4.0)];
+
+        let mut expected = Vec::new();
+        expected.extend(grid(&top, &[left[0], stretched[0], right[0]]));
+        expected.extend(grid(&[(4.0, 12.0, 4.0, 8.0)], &left));
+        // The tiled center is emitted one column of tiles at a time.
+        for column in center.iter() {
+            expected.extend(grid(&middle, &[*column]));
+        }
+        expected.extend(grid(&[(4.0, 12.0, 4.0, 8.0)], &right));
+        expected.extend(grid(&bottom, &[left[0], stretched[0], right[0]]));
+        assert_eq!(rects, expected);
+    }
+
+    #[test]
+    fn tiled_edges_only_repeat_along_their_length() {
+        let rects = nine_slice(FRect::new(0.0, 0.0, 20.0, 8.0), Insets::uniform(4), SliceMode::Tile, SliceMode::Stretch);
+
+        let top = [(0.0, 4.0, 0.0, 4.0)];
+        let bottom = [(4.0, 4.0, 12.0, 4.0)];
+        let columns = [(0.0, 4.0, 0.0, 4.0), (4.0, 8.0, 4.0, 8.0), (12.0, 4.0, 4.0, 4.0), (16.0, 4.0, 12.0, 4.0)];
+        let mut expected = grid(&top, &columns);
+        expected.extend(grid(&bottom, &columns));
+        assert_eq!(rects, expected);
+    }
+
+    #[test]
+    fn zero_insets_draw_only_the_center() {
+        let rects = nine_slice(FRect::new(0.0, 0.0, 32.0, 32.0), Insets::uniform(0), SliceMode::Tile, SliceMode::Stretch);
+
+        assert_eq!(rects, vec![(FRect::new(0.0, 0.0, 32.0, 32.0), FRect::new(0.0, 0.0, 16.0, 16.0))]);
+    }
+
+    #[test]
+    fn insets_shrink_when_the_dest_is_smaller_than_them() {
+        let rects = nine_slice(FRect::new(0.0, 0.0, 4.0, 16.0), Insets::new(6, 4, 2, 4), SliceMode::Stretch, SliceMode::Tile);
+
+        // Horizontally the 8 pixels of insets are squeezed into 4, leaving no room for the center.
+        let rows = [(0.0, 4.0, 0.0, 4.0), (4.0, 8.0, 4.0, 8.0), (12.0, 4.0, 12.0, 4.0)];
+        let columns = [(0.0, 3.0, 0.0, 6.0), (3.0, 1.0, 14.0, 2.0)];
+        assert_eq!(rects, grid(&rows, &columns));
+    }
+
+    #[test]
+    fn premultiplied_modes_premultiply_the_tint() {
+        let tint = [1.0, 0.5, 0.25, 0.5];
+        assert_eq!(BlendMode::Premultiplied.tint(tint), [0.5, 0.25, 0.125, 0.5]);
+        assert_eq!(BlendMode::Multiply.tint(tint), [0.5, 0.25, 0.125, 0.5]);
+    }
+
+    #[test]
+    fn other_modes_keep_the_tint() {
+        let tint = [1.0, 0.5, 0.25, 0.5];
+        for blend_mode in [BlendMode::Alpha, BlendMode::Additive, BlendMode::Replace] {
+            assert_eq!(blend_mode.tint(tint), tint);
+        }
     }
 }
\ No newline at end of file
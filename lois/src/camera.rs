@@ -0,0 +1,69 @@
+use crate::commons::{FRect, Point, ViewSize};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera2D {
+    pub position: Point,
+    pub zoom: f32,
+    pub rotation: f32,
+    pub viewport: Option<FRect>,
+}
+
+impl Camera2D {
+    pub fn new(position: Point) -> Self {
+        Self {
+            position,
+            ..Default::default()
+        }
+    }
+
+    #[inline(always)]
+    pub fn viewport_rect(&self, target_size: ViewSize) -> FRect {
+        self.viewport.unwrap_or(target_size.into())
+    }
+
+    // Maps world coordinates into viewport-local pixels. `position` is the world point
+    // shown at the viewport's top-left corner when unzoomed and unrotated; zoom and
+    // rotation are applied around the viewport's center.
+    pub fn view_matrix(&self, target_size: ViewSize) -> [[f32; 4]; 4] {
+        self.view(target_size).to_cols_array_2d()
+    }
+
+    pub fn world_to_screen(&self, point: Point, target_size: ViewSize) -> Point {
+        let FRect { x, y, .. } = self.viewport_rect(target_size);
+        let local = self
+            .view(target_size)
+            .transform_point3(glam::Vec3::new(point.x, point.y, 0.0));
+        Point::new(local.x + x, local.y + y)
+    }
+
+    pub fn screen_to_world(&self, point: Point, target_size: ViewSize) -> Point {
+        let FRect { x, y, .. } = self.viewport_rect(target_size);
+        let world = self
+            .view(target_size)
+            .inverse()
+            .transform_point3(glam::Vec3::new(point.x - x, point.y - y, 0.0));
+        Point::new(world.x, world.y)
+    }
+
+    fn view(&self, target_size: ViewSize) -> glam::Mat4 {
+        use glam::*;
+
+        let FRect { w, h, .. } = self.viewport_rect(target_size);
+        let (cx, cy) = (w / 2.0, h / 2.0);
+        Mat4::from_translation(Vec3::new(cx, cy, 0.0))
+            * Mat4::from_scale(Vec3::new(self.zoom, self.zoom, 1.0))
+            * Mat4::from_rotation_z(-self.rotation)
+            * Mat4::from_translation(Vec3::new(-self.position.x - cx, -self.position.y - cy, 0.0))
+    }
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self {
+            position: Point::default(),
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport: None,
+        }
+    }
+}
@@ -1,10 +1,10 @@
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct ViewSize {
     pub width: u32,
     pub height: u32,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -12,7 +12,7 @@ pub struct Color {
     pub a: u8,
 }
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct Rect {
     pub x: i32,
     pub y: i32,
@@ -20,16 +20,52 @@ pub struct Rect {
     pub h: u32,
 }
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FRect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Insets {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Point {
     pub x: f32,
     pub y: f32,
 }
 
 impl Color {
-    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+    pub const WHITE: Color = Color::new(255, 255, 255, 255);
+    pub const BLACK: Color = Color::new(0, 0, 0, 255);
+    pub const TRANSPARENT: Color = Color::new(0, 0, 0, 0);
+
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
         Self { r, g, b, a }
     }
+
+    #[inline(always)]
+    pub fn with_alpha(self, a: u8) -> Self {
+        Self { a, ..self }
+    }
+
+    #[inline(always)]
+    pub(crate) fn normalized(self) -> [f32; 4] {
+        let max = u8::MAX as f32;
+        [
+            self.r as f32 / max,
+            self.g as f32 / max,
+            self.b as f32 / max,
+            self.a as f32 / max,
+        ]
+    }
 }
 
 impl Point {
@@ -50,13 +86,16 @@ impl Rect {
         Self { x, y, w, h }
     }
 
-    #[inline(always)]
-    pub(crate) fn normalized(self, width: f32, height: f32) -> [f32; 4] {
-        let x = self.x as f32 / width;
-        let y = self.y as f32 / height;
-        let w = self.w as f32 / width;
-        let h = self.h as f32 / height;
-        [x, y, w, h]
+    pub fn intersection(self, other: Rect) -> Option<Rect> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x + self.w as i32).min(other.x + other.w as i32);
+        let bottom = (self.y + self.h as i32).min(other.y + other.h as i32);
+        if right > left && bottom > top {
+            Some(Rect::new(left, top, (right - left) as u32, (bottom - top) as u32))
+        } else {
+            None
+        }
     }
 
     #[inline(always)]
@@ -69,6 +108,51 @@ impl Rect {
     }
 }
 
+impl FRect {
+    #[inline(always)]
+    pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    #[inline(always)]
+    pub(crate) fn normalized(self, width: f32, height: f32) -> [f32; 4] {
+        [self.x / width, self.y / height, self.w / width, self.h / height]
+    }
+
+    #[inline(always)]
+    pub fn center(self) -> Point {
+        Point::new(self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
+
+    #[inline(always)]
+    pub fn intersects(self, other: FRect) -> bool {
+        self.x < other.x + other.w
+            && other.x < self.x + self.w
+            && self.y < other.y + other.h
+            && other.y < self.y + self.h
+    }
+
+    pub fn union(self, other: FRect) -> FRect {
+        let left = self.x.min(other.x);
+        let top = self.y.min(other.y);
+        let right = (self.x + self.w).max(other.x + other.w);
+        let bottom = (self.y + self.h).max(other.y + other.h);
+        FRect::new(left, top, right - left, bottom - top)
+    }
+}
+
+impl Insets {
+    #[inline(always)]
+    pub fn new(left: u32, top: u32, right: u32, bottom: u32) -> Self {
+        Self { left, top, right, bottom }
+    }
+
+    #[inline(always)]
+    pub fn uniform(inset: u32) -> Self {
+        Self::new(inset, inset, inset, inset)
+    }
+}
+
 impl ViewSize {
     #[inline(always)]
     pub fn new(width: u32, height: u32) -> Self {
@@ -76,21 +160,41 @@ impl ViewSize {
     }
 }
 
-impl Into<[f32; 4]> for Rect {
+impl From<Rect> for [f32; 4] {
+    #[inline(always)]
+    fn from(rect: Rect) -> Self {
+        [rect.x as f32, rect.y as f32, rect.w as f32, rect.h as f32]
+    }
+}
+
+impl From<FRect> for [f32; 4] {
+    #[inline(always)]
+    fn from(rect: FRect) -> Self {
+        [rect.x, rect.y, rect.w, rect.h]
+    }
+}
+
+impl From<Rect> for FRect {
     #[inline(always)]
-    fn into(self) -> [f32; 4] {
-        [self.x as f32, self.y as f32, self.w as f32, self.h as f32]
+    fn from(rect: Rect) -> Self {
+        FRect::new(rect.x as f32, rect.y as f32, rect.w as f32, rect.h as f32)
+    }
+}
+
+impl From<Point> for [f32; 2] {
+    fn from(point: Point) -> Self {
+        [point.x, point.y]
     }
 }
 
-impl Into<[f32; 2]> for Point {
-    fn into(self) -> [f32; 2] {
-        [self.x, self.y]
+impl From<ViewSize> for Rect {
+    fn from(size: ViewSize) -> Self {
+        Rect::new(0, 0, size.width, size.height)
     }
 }
 
-impl Into<Rect> for ViewSize {
-    fn into(self) -> Rect {
-        Rect::new(0, 0, self.width, self.height)
+impl From<ViewSize> for FRect {
+    fn from(size: ViewSize) -> Self {
+        FRect::new(0.0, 0.0, size.width as f32, size.height as f32)
     }
 }
\ No newline at end of file
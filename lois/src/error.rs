@@ -0,0 +1,108 @@
+use std::fmt;
+
+use crate::{commons::{Rect, ViewSize}, shader::Shader, texture::{Texture, TextureFormat}};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub type BackendError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+#[derive(Debug)]
+pub enum Error {
+    TextureNotFound(Texture),
+    TargetNotFound(Texture),
+    ShaderNotFound(Shader),
+    InvalidShader(String),
+    TooManyUniformTextures { max: usize, actual: usize },
+    NotARenderTarget(Texture),
+    SelfTargetingBatch(Texture),
+    UnsupportedFormat(TextureFormat),
+    InvalidTextureData { expected: usize, actual: usize },
+    InvalidTextureRect(Rect),
+    AtlasImageTooLarge(ViewSize),
+    InvalidMeshIndex { index: u32, vertices: usize },
+    InvalidFont(String),
+    GlyphCacheFull(ViewSize),
+    ScreenCaptureDisabled,
+    AdapterNotFound,
+    DeviceRequestFailed(BackendError),
+    SurfaceLost(BackendError),
+    Backend(BackendError),
+}
+
+impl Error {
+    pub fn backend<E>(error: E) -> Self
+    where
+        E: Into<BackendError>,
+    {
+        Self::Backend(error.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TextureNotFound(texture) => write!(f, "Texture {} not found.", texture.id()),
+            Self::TargetNotFound(texture) => write!(f, "Target texture {} not found.", texture.id()),
+            Self::ShaderNotFound(shader) => write!(f, "Shader {} not found.", shader.id()),
+            Self::InvalidShader(reason) => write!(f, "Invalid shader: {}.", reason),
+            Self::TooManyUniformTextures { max, actual } => write!(
+                f,
+                "Uniform block has {} textures, at most {} are supported.",
+                actual, max
+            ),
+            Self::NotARenderTarget(texture) => write!(
+                f,
+                "Target texture {} is not usable as RenderTarget.",
+                texture.id()
+            ),
+            Self::SelfTargetingBatch(texture) => write!(
+                f,
+                "A batch's texture cannot be the same as its target (texture {}).",
+                texture.id()
+            ),
+            Self::UnsupportedFormat(format) => write!(f, "Texture format {:?} not supported.", format),
+            Self::InvalidTextureData { expected, actual } => write!(
+                f,
+                "Texture data has {} bytes, expected {}.",
+                actual, expected
+            ),
+            Self::InvalidTextureRect(rect) => write!(
+                f,
+                "Rect of {}x{} at ({}, {}) is out of the texture bounds.",
+                rect.w, rect.h, rect.x, rect.y
+            ),
+            Self::AtlasImageTooLarge(size) => write!(
+                f,
+                "Image of {}x{} does not fit in an atlas page.",
+                size.width, size.height
+            ),
+            Self::InvalidMeshIndex { index, vertices } => write!(
+                f,
+                "Mesh index {} is out of bounds for {} vertices.",
+                index, vertices
+            ),
+            Self::InvalidFont(reason) => write!(f, "Invalid font: {}.", reason),
+            Self::GlyphCacheFull(size) => write!(
+                f,
+                "Glyph of {}x{} does not fit in the glyph cache.",
+                size.width, size.height
+            ),
+            Self::ScreenCaptureDisabled => write!(f, "Screen capture must be enabled to read the screen."),
+            Self::AdapterNotFound => write!(f, "Unable to request a suitable adapter."),
+            Self::DeviceRequestFailed(_) => write!(f, "Unable to request a device."),
+            Self::SurfaceLost(_) => write!(f, "The render surface was lost."),
+            Self::Backend(_) => write!(f, "Backend error."),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::DeviceRequestFailed(source) | Self::SurfaceLost(source) | Self::Backend(source) => {
+                Some(source.as_ref())
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,568 @@
+use std::collections::HashMap;
+
+use rusttype::Scale;
+
+use crate::{
+    backend::Backend,
+    batch::TextureBatchOptions,
+    commons::{Color, FRect, Point, Rect, ViewSize},
+    error::{Error, Result},
+    graphics::{DrawOptions, Graphics},
+    layout::{LayoutOptions, TextLayout, TextMetrics},
+    texture::{Texture, TextureFormat, TextureLoadOptions},
+};
+
+const PADDING: u32 = 1;
+const SHELF_ALIGNMENT: u32 = 4;
+
+#[derive(Clone)]
+pub struct Font(rusttype::Font<'static>);
+
+#[derive(Copy, Clone, Debug)]
+pub struct ScaledFont<'a> {
+    font: &'a Font,
+    size: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FontId(usize);
+
+#[derive(Copy, Clone, Debug)]
+pub struct TextOptions {
+    pub target: Option<Texture>,
+    pub position: Point,
+    pub size: f32,
+    pub color: Color,
+}
+
+pub struct GlyphCache {
+    texture: Texture,
+    size: ViewSize,
+    fonts: Vec<Font>,
+    glyphs: HashMap<GlyphKey, CachedGlyph>,
+    shelves: Vec<Shelf>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font: FontId,
+    glyph: u16,
+    size: u32,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct CachedGlyph {
+    rect: Rect,
+    offset: Point,
+    shelf: usize,
+    last_used: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    slots: Vec<Slot>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Slot {
+    x: u32,
+    width: u32,
+    glyph: Option<GlyphKey>,
+}
+
+impl Font {
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        rusttype::Font::try_from_vec(data)
+            .map(Self)
+            .ok_or_else(|| Error::InvalidFont("unable to parse font data".to_string()))
+    }
+
+    pub fn line_height(&self, size: f32) -> f32 {
+        let metrics = self.0.v_metrics(Scale::uniform(size));
+        metrics.ascent - metrics.descent + metrics.line_gap
+    }
+
+    pub fn ascent(&self, size: f32) -> f32 {
+        self.0.v_metrics(Scale::uniform(size)).ascent
+    }
+
+    pub fn advance(&self, character: char, size: f32) -> f32 {
+        self.0.glyph(character).scaled(Scale::uniform(size)).h_metrics().advance_width
+    }
+
+    pub fn kerning(&self, first: char, second: char, size: f32) -> f32 {
+        self.0.pair_kerning(Scale::uniform(size), first, second)
+    }
+
+    pub fn scaled(&self, size: f32) -> ScaledFont<'_> {
+        ScaledFont { font: self, size }
+    }
+}
+
+impl TextMetrics for ScaledFont<'_> {
+    fn line_height(&self) -> f32 {
+        self.font.line_height(self.size)
+    }
+
+    fn advance(&self, character: char) -> f32 {
+        self.font.advance(character, self.size)
+    }
+
+    fn kerning(&self, first: char, second: char) -> f32 {
+        self.font.kerning(first, second, self.size)
+    }
+}
+
+impl std::fmt::Debug for Font {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Font")
+            .field("glyphs", &self.0.glyph_count())
+            .finish()
+    }
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            target: None,
+            position: Point::default(),
+            size: 16.0,
+            color: Color::WHITE,
+        }
+    }
+}
+
+impl GlyphCache {
+    pub fn new<B>(graphics: &mut Graphics<B>, size: ViewSize) -> Result<Self>
+    where
+        B: Backend,
+    {
+        let data = vec![0; size.width as usize * size.height as usize];
+        let texture = graphics.load_texture(TextureLoadOptions {
+            name: Some("Glyph Cache"),
+            data: Some(&data),
+            size,
+            format: TextureFormat::R8Unorm,
+            ..Default::default()
+        })?;
+        Ok(Self {
+            texture,
+            size,
+            fonts: Vec::new(),
+            glyphs: HashMap::new(),
+            shelves: Vec::new(),
+        })
+    }
+
+    pub fn add_font(&mut self, font: Font) -> FontId {
+        self.fonts.push(font);
+        FontId(self.fonts.len() - 1)
+    }
+
+    pub fn font(&self, font: FontId) -> Option<&Font> {
+        self.fonts.get(font.0)
+    }
+
+    pub fn texture(&self) -> Texture {
+        self.texture
+    }
+
+    pub fn size(&self) -> ViewSize {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.shelves.clear();
+    }
+
+    pub fn unload<B>(self, graphics: &mut Graphics<B>)
+    where
+        B: Backend,
+    {
+        graphics.unload_texture(self.texture);
+    }
+
+    pub fn draw_text<B>(
+        &mut self,
+        graphics: &mut Graphics<B>,
+        font: FontId,
+        text: &str,
+        options: TextOptions,
+    ) -> Result<()>
+    where
+        B: Backend,
+    {
+        let metrics = self.try_get_font(font)?.scaled(options.size);
+        let layout = TextLayout::new(&metrics, text, LayoutOptions::default());
+        self.draw_layout(graphics, font, &layout, options)
+    }
+
+    // Draws a layout built with this font's metrics at `options.size`, offset by
+    // `options.position`.
+    pub fn draw_layout<B>(
+        &mut self,
+        graphics: &mut Graphics<B>,
+        font: FontId,
+        layout: &TextLayout,
+        options: TextOptions,
+    ) -> Result<()>
+    where
+        B: Backend,
+    {
+        let TextOptions {
+            target,
+            position,
+            size,
+            color,
+        } = options;
+        let face = self.try_get_font(font)?.0.clone();
+        let scale = Scale::uniform(size);
+        let ascent = face.v_metrics(scale).ascent;
+        let frame = graphics.frame_count();
+        let mut quads = Vec::with_capacity(layout.glyphs().len());
+
+        for layout_glyph in layout.glyphs() {
+            let glyph = face.glyph(layout_glyph.character).scaled(scale);
+            let key = GlyphKey {
+                font,
+                glyph: glyph.id().0,
+                size: size.to_bits(),
+            };
+            let cached = match self.glyphs.get_mut(&key) {
+                Some(cached) => {
+                    cached.last_used = frame;
+                    Some(*cached)
+                }
+                None => {
+                    let glyph = glyph.positioned(rusttype::point(0.0, 0.0));
+                    match glyph.pixel_bounding_box() {
+                        Some(bounds) => {
+                            let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+                            let mut pixels = vec![0u8; ((width + PADDING) * (height + PADDING)) as usize];
+                            let stride = width + PADDING;
+                            glyph.draw(|x, y, coverage| {
+                                pixels[(y * stride + x) as usize] = (coverage * 255.0).round() as u8;
+                            });
+                            let cached = self.insert(key, width, height, Point::new(bounds.min.x as f32, bounds.min.y as f32), frame)?;
+                            let Rect { x, y, .. } = cached.rect;
+                            graphics.update_texture(
+                                self.texture,
+                                Some(Rect::new(x, y, width + PADDING, height + PADDING)),
+                                &pixels,
+                            )?;
+                            Some(cached)
+                        }
+                        None => None,
+                    }
+                }
+            };
+
+            if let Some(CachedGlyph { rect, offset, .. }) = cached {
+                let pen = Point::new(
+                    position.x + layout_glyph.rect.x,
+                    position.y + layout_glyph.rect.y + ascent,
+                );
+                let dest_rect = FRect::new(
+                    pen.x.round() + offset.x,
+                    pen.y.round() + offset.y,
+                    rect.w as f32,
+                    rect.h as f32,
+                );
+                quads.push((rect, dest_rect));
+            }
+        }
+
+        if quads.is_empty() {
+            return Ok(());
+        }
+
+        let mut batch = graphics.new_batch(TextureBatchOptions::new(self.texture, target))?;
+        for (src_rect, dest_rect) in quads {
+            batch = batch.draw(DrawOptions {
+                src_rect: Some(src_rect.into()),
+                dest_rect: Some(dest_rect),
+                color,
+                ..Default::default()
+            });
+        }
+        Ok(())
+    }
+
+    fn try_get_font(&self, font: FontId) -> Result<&Font> {
+        self.font(font)
+            .ok_or_else(|| Error::InvalidFont(format!("font {} is not registered", font.0)))
+    }
+
+    fn insert(
+        &mut self,
+        key: GlyphKey,
+        width: u32,
+        height: u32,
+        offset: Point,
+        frame: u64,
+    ) -> Result<CachedGlyph> {
+        let (slot_width, slot_height) = (width + PADDING, height + PADDING);
+        loop {
+            if let Some((shelf, x)) = self.allocate(slot_width, slot_height, key) {
+                let cached = CachedGlyph {
+                    rect: Rect::new(x as i32, self.shelves[shelf].y as i32, width, height),
+                    offset,
+                    shelf,
+                    last_used: frame,
+                };
+                self.glyphs.insert(key, cached);
+                return Ok(cached);
+            }
+            if !self.evict_least_recently_used(slot_height, frame) {
+                return Err(Error::GlyphCacheFull(ViewSize::new(width, height)));
+            }
+        }
+    }
+
+    // Places the glyph in the tightest free slot of a tall enough shelf, opening a new
+    // shelf below the last one if none fits.
+    fn allocate(&mut self, width: u32, height: u32, key: GlyphKey) -> Option<(usize, u32)> {
+        let best = self
+            .shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, shelf)| shelf.height >= height)
+            .flat_map(|(index, shelf)| {
+                shelf
+                    .slots
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, slot)| slot.glyph.is_none() && slot.width >= width)
+                    .map(move |(slot, free)| ((shelf.height, free.width), index, slot))
+            })
+            .min_by_key(|&(fit, ..)| fit)
+            .map(|(_, shelf, slot)| (shelf, slot));
+
+        let (shelf, slot) = match best {
+            Some(best) => best,
+            None => {
+                let y = self
+                    .shelves
+                    .last()
+                    .map(|shelf| shelf.y + shelf.height)
+                    .unwrap_or(PADDING);
+                let shelf_height = height.div_ceil(SHELF_ALIGNMENT) * SHELF_ALIGNMENT;
+                let shelf_width = self.size.width.saturating_sub(PADDING);
+                if y + shelf_height > self.size.height || width > shelf_width {
+                    return None;
+                }
+                self.shelves.push(Shelf {
+                    y,
+                    height: shelf_height,
+                    slots: vec![Slot {
+                        x: PADDING,
+                        width: shelf_width,
+                        glyph: None,
+                    }],
+                });
+                (self.shelves.len() - 1, 0)
+            }
+        };
+
+        let slots = &mut self.shelves[shelf].slots;
+        let free = slots[slot];
+        slots[slot] = Slot {
+            x: free.x,
+            width,
+            glyph: Some(key),
+        };
+        if free.width > width {
+            slots.insert(
+                slot + 1,
+                Slot {
+                    x: free.x + width,
+                    width: free.width - width,
+                    glyph: None,
+                },
+            );
+        }
+        Some((shelf, free.x))
+    }
+
+    // Frees the least recently used glyph of a tall enough shelf, skipping glyphs used in
+    // the current frame since their quads are still waiting to be presented. When no such
+    // shelf exists, the last shelf is dropped instead to make room for a taller one.
+    fn evict_least_recently_used(&mut self, height: u32, frame: u64) -> bool {
+        let shelves = &self.shelves;
+        let victim = self
+            .glyphs
+            .iter()
+            .filter(|(_, cached)| cached.last_used < frame && shelves[cached.shelf].height >= height)
+            .min_by_key(|(_, cached)| cached.last_used)
+            .map(|(key, cached)| (*key, *cached));
+
+        let (key, cached) = match victim {
+            Some(victim) => victim,
+            None => return self.evict_last_shelf(frame),
+        };
+        self.glyphs.remove(&key);
+
+        let slots = &mut self.shelves[cached.shelf].slots;
+        if let Some(index) = slots.iter().position(|slot| slot.glyph == Some(key)) {
+            slots[index].glyph = None;
+            if index + 1 < slots.len() && slots[index + 1].glyph.is_none() {
+                slots[index].width += slots.remove(index + 1).width;
+            }
+            if index > 0 && slots[index - 1].glyph.is_none() {
+                slots[index - 1].width += slots.remove(index).width;
+            }
+        }
+        true
+    }
+
+    fn evict_last_shelf(&mut self, frame: u64) -> bool {
+        let last = match self.shelves.len().checked_sub(1) {
+            Some(last) => last,
+            None => return false,
+        };
+        let glyphs = &self.glyphs;
+        let in_use = self.shelves[last]
+            .slots
+            .iter()
+            .filter_map(|slot| slot.glyph)
+            .any(|key| glyphs[&key].last_used >= frame);
+        if in_use {
+            return false;
+        }
+
+        for key in self.shelves.pop().unwrap().slots.iter().filter_map(|slot| slot.glyph) {
+            self.glyphs.remove(&key);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(width: u32, height: u32) -> GlyphCache {
+        GlyphCache {
+            texture: Texture::new(0),
+            size: ViewSize::new(width, height),
+            fonts: Vec::new(),
+            glyphs: HashMap::new(),
+            shelves: Vec::new(),
+        }
+    }
+
+    fn key(glyph: u16) -> GlyphKey {
+        GlyphKey {
+            font: FontId(0),
+            glyph,
+            size: 16.0f32.to_bits(),
+        }
+    }
+
+    fn insert(cache: &mut GlyphCache, glyph: u16, width: u32, height: u32, frame: u64) -> Result<Rect> {
+        cache
+            .insert(key(glyph), width, height, Point::default(), frame)
+            .map(|cached| cached.rect)
+    }
+
+    #[test]
+    fn allocates_glyphs_side_by_side_on_a_shelf() {
+        let mut cache = cache(32, 32);
+
+        assert_eq!(insert(&mut cache, 1, 7, 7, 0).unwrap(), Rect::new(1, 1, 7, 7));
+        assert_eq!(insert(&mut cache, 2, 5, 5, 0).unwrap(), Rect::new(9, 1, 5, 5));
+        assert_eq!(cache.shelves.len(), 1);
+        assert_eq!(cache.shelves[0].height, 8);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn opens_a_new_shelf_for_taller_glyphs() {
+        let mut cache = cache(32, 32);
+
+        insert(&mut cache, 1, 7, 7, 0).unwrap();
+        assert_eq!(insert(&mut cache, 2, 10, 10, 0).unwrap(), Rect::new(1, 9, 10, 10));
+        assert_eq!(cache.shelves.len(), 2);
+        assert_eq!(cache.shelves[1].height, 12);
+    }
+
+    #[test]
+    fn wraps_to_a_new_shelf_when_the_row_is_full() {
+        let mut cache = cache(16, 32);
+
+        assert_eq!(insert(&mut cache, 1, 7, 7, 0).unwrap(), Rect::new(1, 1, 7, 7));
+        assert_eq!(insert(&mut cache, 2, 7, 7, 0).unwrap(), Rect::new(1, 9, 7, 7));
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_glyph() {
+        let mut cache = cache(16, 9);
+
+        insert(&mut cache, 1, 6, 7, 0).unwrap();
+        insert(&mut cache, 2, 6, 7, 1).unwrap();
+        assert_eq!(insert(&mut cache, 3, 6, 7, 2).unwrap(), Rect::new(1, 1, 6, 7));
+
+        assert!(!cache.glyphs.contains_key(&key(1)));
+        assert!(cache.glyphs.contains_key(&key(2)));
+        assert!(cache.glyphs.contains_key(&key(3)));
+    }
+
+    #[test]
+    fn keeps_glyphs_used_in_the_current_frame() {
+        let mut cache = cache(16, 9);
+
+        insert(&mut cache, 1, 6, 7, 0).unwrap();
+        insert(&mut cache, 2, 6, 7, 0).unwrap();
+        assert!(matches!(insert(&mut cache, 3, 6, 7, 0), Err(Error::GlyphCacheFull(_))));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn reinserts_an_evicted_glyph_in_place_of_the_next_oldest() {
+        let mut cache = cache(16, 9);
+
+        insert(&mut cache, 1, 6, 7, 0).unwrap();
+        insert(&mut cache, 2, 6, 7, 1).unwrap();
+        insert(&mut cache, 3, 6, 7, 2).unwrap();
+        assert_eq!(insert(&mut cache, 1, 6, 7, 3).unwrap(), Rect::new(8, 1, 6, 7));
+
+        assert!(!cache.glyphs.contains_key(&key(2)));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn merges_freed_slots_for_wider_glyphs() {
+        let mut cache = cache(16, 9);
+
+        insert(&mut cache, 1, 6, 7, 0).unwrap();
+        insert(&mut cache, 2, 6, 7, 1).unwrap();
+        assert_eq!(insert(&mut cache, 3, 13, 7, 2).unwrap(), Rect::new(1, 1, 13, 7));
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.shelves[0].slots.len(), 2);
+    }
+
+    #[test]
+    fn drops_the_last_shelf_to_fit_a_taller_glyph() {
+        let mut cache = cache(16, 16);
+
+        insert(&mut cache, 1, 6, 7, 0).unwrap();
+        insert(&mut cache, 2, 6, 7, 0).unwrap();
+        assert_eq!(insert(&mut cache, 3, 6, 10, 1).unwrap(), Rect::new(1, 1, 6, 10));
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.shelves.len(), 1);
+        assert_eq!(cache.shelves[0].height, 12);
+    }
+}
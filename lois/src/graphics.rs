@@ -1,11 +1,20 @@
-use std::ops::Range;
+use std::{collections::HashMap, ops::Range};
 
 use crate::{
     backend::Backend,
-    batch::{TextureBatch, TextureBatchOptions},
-    commons::{Color, Point, Rect, ViewSize},
+    batch::{BlendMode, TextureBatch, TextureBatchOptions},
+    camera::Camera2D,
+    commons::{Color, FRect, Point, Rect, ViewSize},
+    error::{Error, Result},
+    merge::{self, MergeStats},
+    mesh::{MeshIndices, MeshOptions, MeshVertex},
+    postprocess::{PostProcess, PostProcessor},
     quad::Quad,
-    texture::{Texture, TextureLoadOptions, TextureQuery, TextureUsage},
+    shader::{Shader, ShaderLoadOptions, UniformBlock, MAX_UNIFORM_TEXTURES},
+    shape::{ShapeBatch, ShapeBatchOptions},
+    sort,
+    texture::{SamplerOptions, Texture, TextureLoadOptions, TextureQuery, TextureUsage},
+    transform::Transform2D,
 };
 
 pub struct Graphics<B>
@@ -16,33 +25,135 @@ where
     viewport_size: ViewSize,
     commands: Vec<DrawCommand>,
     quads: Vec<Quad>,
+    layers: Vec<i32>,
+    vertices: Vec<MeshVertex>,
+    indices: Vec<u32>,
+    layer_sorting: bool,
+    batch_merging: bool,
+    merge_stats: MergeStats,
+    cameras: HashMap<Option<Texture>, Camera2D>,
+    scissors: HashMap<Option<Texture>, Rect>,
+    // Each pushed transform along with the accumulated one it results in.
+    transforms: Vec<(Transform2D, Transform2D)>,
+    frame_count: u64,
+    post_process: Option<PostProcess>,
+    post_processor: PostProcessor,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum DrawCommand {
     DrawTextureBatch(DrawTextureBatchCommand),
+    DrawShapes(DrawShapesCommand),
+    DrawMesh(DrawMeshCommand),
     Clear(ClearCommand),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ClearCommand {
     pub target: Option<Texture>,
     pub color: Color,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct DrawTextureBatchCommand {
     pub texture: Texture,
     pub target: Option<Texture>,
+    pub blend_mode: BlendMode,
+    pub shader: Option<Shader>,
+    pub uniforms: Option<UniformBlock>,
+    pub sampler: Option<SamplerOptions>,
+    pub camera: Option<Camera2D>,
+    pub scissor: Option<Rect>,
     pub range: Range<usize>,
 }
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Clone, Debug)]
+pub struct DrawShapesCommand {
+    pub target: Option<Texture>,
+    pub blend_mode: BlendMode,
+    pub camera: Option<Camera2D>,
+    pub scissor: Option<Rect>,
+    pub range: Range<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DrawMeshCommand {
+    pub texture: Texture,
+    pub target: Option<Texture>,
+    pub blend_mode: BlendMode,
+    pub camera: Option<Camera2D>,
+    pub scissor: Option<Rect>,
+    pub range: Range<usize>,
+}
+
+#[derive(Copy, Clone, Debug)]
 pub struct DrawOptions {
-    pub src_rect: Option<Rect>,
-    pub dest_rect: Option<Rect>,
+    pub src_rect: Option<FRect>,
+    pub dest_rect: Option<FRect>,
     pub rotation_center: Option<Point>,
     pub rotation_angle: f32,
+    pub color: Color,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub layer: i32,
+}
+
+impl DrawCommand {
+    pub(crate) fn target_mut(&mut self) -> &mut Option<Texture> {
+        match self {
+            DrawCommand::DrawTextureBatch(command) => &mut command.target,
+            DrawCommand::DrawShapes(command) => &mut command.target,
+            DrawCommand::DrawMesh(command) => &mut command.target,
+            DrawCommand::Clear(command) => &mut command.target,
+        }
+    }
+}
+
+impl DrawTextureBatchCommand {
+    pub fn batches_with(&self, other: &DrawTextureBatchCommand) -> bool {
+        self.texture == other.texture
+            && self.target == other.target
+            && self.blend_mode == other.blend_mode
+            && self.shader == other.shader
+            && self.uniforms == other.uniforms
+            && self.sampler == other.sampler
+            && self.camera == other.camera
+            && self.scissor == other.scissor
+    }
+}
+
+impl DrawShapesCommand {
+    pub fn batches_with(&self, other: &DrawShapesCommand) -> bool {
+        self.target == other.target
+            && self.blend_mode == other.blend_mode
+            && self.camera == other.camera
+            && self.scissor == other.scissor
+    }
+}
+
+impl DrawMeshCommand {
+    pub fn batches_with(&self, other: &DrawMeshCommand) -> bool {
+        self.texture == other.texture
+            && self.target == other.target
+            && self.blend_mode == other.blend_mode
+            && self.camera == other.camera
+            && self.scissor == other.scissor
+    }
+}
+
+impl Default for DrawOptions {
+    fn default() -> Self {
+        Self {
+            src_rect: None,
+            dest_rect: None,
+            rotation_center: None,
+            rotation_angle: 0.0,
+            color: Color::WHITE,
+            flip_x: false,
+            flip_y: false,
+            layer: 0,
+        }
+    }
 }
 
 impl<B> Graphics<B>
@@ -57,6 +168,18 @@ where
             viewport_size,
             commands,
             quads,
+            layers: Vec::with_capacity(10000),
+            vertices: Vec::with_capacity(10000),
+            indices: Vec::with_capacity(10000),
+            layer_sorting: false,
+            batch_merging: false,
+            merge_stats: MergeStats::default(),
+            cameras: HashMap::new(),
+            scissors: HashMap::new(),
+            transforms: Vec::new(),
+            frame_count: 0,
+            post_process: None,
+            post_processor: PostProcessor::default(),
             backend,
         }
     }
@@ -64,27 +187,119 @@ where
     pub fn new_batch<'a>(
         &'a mut self,
         options: TextureBatchOptions,
-    ) -> Result<TextureBatch<'a>, String> {
-        self.push_command_if_necessary(options);
+    ) -> Result<TextureBatch<'a>> {
         let view_size = self.try_get_batch_view_size(options)?;
         let TextureQuery {
-            size: texture_size, ..
+            size: texture_size,
+            sampler: texture_sampler,
+            ..
         } = self
             .backend
             .query_texture(options.texture)
-            .ok_or(String::from("Texture not found."))?;
-        let command = self.commands.last_mut().unwrap();
-        match command {
-            DrawCommand::DrawTextureBatch(command) => {
-                return Ok(TextureBatch::new(
-                    view_size,
-                    texture_size,
-                    &mut self.quads,
-                    command,
-                ));
+            .ok_or(Error::TextureNotFound(options.texture))?;
+        let transform = self.transform();
+        // Overriding the sampler with the texture's own one is the same as not overriding it.
+        self.push_command_if_necessary(TextureBatchOptions {
+            sampler: options.sampler.filter(|&sampler| sampler != texture_sampler),
+            ..options
+        });
+        if let Some(DrawCommand::DrawTextureBatch(command)) = self.commands.last_mut() {
+            Ok(TextureBatch::new(
+                view_size,
+                texture_size,
+                transform,
+                &mut self.quads,
+                &mut self.layers,
+                command,
+            ))
+        } else {
+            panic!("Last command at command queue should be a DrawTextureBatch one.")
+        }
+    }
+
+    pub fn new_shape_batch<'a>(
+        &'a mut self,
+        options: ShapeBatchOptions,
+    ) -> Result<ShapeBatch<'a>> {
+        self.try_get_target_view_size(options.target)?;
+        let transform = self.transform();
+        let command = DrawShapesCommand {
+            target: options.target,
+            blend_mode: options.blend_mode,
+            camera: self.camera(options.target),
+            scissor: self.scissor(options.target),
+            range: self.indices.len()..self.indices.len(),
+        };
+        match self.commands.last() {
+            Some(DrawCommand::DrawShapes(last)) if last.batches_with(&command) => {}
+            _ => self.commands.push(DrawCommand::DrawShapes(command)),
+        }
+        if let Some(DrawCommand::DrawShapes(command)) = self.commands.last_mut() {
+            Ok(ShapeBatch::new(
+                transform,
+                &mut self.vertices,
+                &mut self.indices,
+                command,
+            ))
+        } else {
+            panic!("Last command at command queue should be a DrawShapes one.")
+        }
+    }
+
+    pub fn draw_mesh<'a, I>(
+        &mut self,
+        options: MeshOptions,
+        vertices: &[MeshVertex],
+        indices: I,
+    ) -> Result<()>
+    where
+        I: Into<MeshIndices<'a>>,
+    {
+        let indices = indices.into();
+        if let Some(target) = options.target {
+            if target == options.texture {
+                return Err(Error::SelfTargetingBatch(target));
+            }
+        }
+        self.try_get_target_view_size(options.target)?;
+        self.query_texture(options.texture)
+            .ok_or(Error::TextureNotFound(options.texture))?;
+        if let Some(index) = indices.iter().find(|&index| index as usize >= vertices.len()) {
+            return Err(Error::InvalidMeshIndex {
+                index,
+                vertices: vertices.len(),
+            });
+        }
+
+        let command = DrawMeshCommand {
+            texture: options.texture,
+            target: options.target,
+            blend_mode: options.blend_mode,
+            camera: self.camera(options.target),
+            scissor: self.scissor(options.target),
+            range: self.indices.len()..self.indices.len(),
+        };
+        match self.commands.last() {
+            Some(DrawCommand::DrawMesh(last)) if last.batches_with(&command) => {}
+            _ => self.commands.push(DrawCommand::DrawMesh(command)),
+        }
+
+        let transform = self.transform();
+        let first = self.vertices.len() as u32;
+        self.vertices.extend(vertices.iter().map(|vertex| {
+            let [x, y] = vertex.position;
+            MeshVertex {
+                position: transform.transform_point(Point::new(x, y)).into(),
+                color: options.blend_mode.tint(vertex.color),
+                ..*vertex
             }
-            _ => panic!("Last command at command queue should be a DrawTextureBatch one."),
+        }));
+        self.indices.extend(indices.iter().map(|index| first + index));
+
+        if let Some(DrawCommand::DrawMesh(command)) = self.commands.last_mut() {
+            command.range.end = self.indices.len();
         }
+        Ok(())
     }
 
     pub fn clear(&mut self, color: Color, target: Option<Texture>) {
@@ -92,24 +307,166 @@ where
             .push(DrawCommand::Clear(ClearCommand { target, color }));
     }
 
-    pub fn present(&mut self) {
-        self.backend.present(&self.commands, &self.quads);
+    pub fn present(&mut self) -> Result<()> {
+        let result = self.present_commands();
         self.commands.clear();
         self.quads.clear();
+        self.layers.clear();
+        self.vertices.clear();
+        self.indices.clear();
+        self.frame_count += 1;
+        result
+    }
+
+    pub(crate) fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn set_post_process(&mut self, post_process: Option<PostProcess>) {
+        if post_process.is_none() {
+            self.post_processor.unload(&mut self.backend);
+        }
+        self.post_process = post_process;
+    }
+
+    pub fn post_process(&self) -> Option<&PostProcess> {
+        self.post_process.as_ref()
+    }
+
+    pub fn post_process_mut(&mut self) -> Option<&mut PostProcess> {
+        self.post_process.as_mut()
+    }
+
+    pub fn set_layer_sorting(&mut self, enabled: bool) {
+        self.layer_sorting = enabled;
+    }
+
+    pub fn layer_sorting(&self) -> bool {
+        self.layer_sorting
+    }
+
+    pub fn set_batch_merging(&mut self, enabled: bool) {
+        self.batch_merging = enabled;
+        self.merge_stats = MergeStats::default();
+    }
+
+    pub fn batch_merging(&self) -> bool {
+        self.batch_merging
+    }
+
+    pub fn merge_stats(&self) -> MergeStats {
+        self.merge_stats
     }
 
-    pub fn load_texture(&mut self, options: TextureLoadOptions) -> Result<Texture, String> {
+    pub fn load_texture(&mut self, options: TextureLoadOptions) -> Result<Texture> {
         self.backend.load_texture(options)
     }
 
+    pub fn update_texture(&mut self, texture: Texture, rect: Option<Rect>, data: &[u8]) -> Result<()> {
+        let TextureQuery { size, format, .. } = self
+            .query_texture(texture)
+            .ok_or(Error::TextureNotFound(texture))?;
+        let rect = try_get_texture_rect(size, rect)?;
+        if rect.w == 0 || rect.h == 0 {
+            return Ok(());
+        }
+        let expected = format.bytes_per_pixel() * rect.w as usize * rect.h as usize;
+        if data.len() < expected {
+            return Err(Error::InvalidTextureData {
+                expected,
+                actual: data.len(),
+            });
+        }
+        self.backend.update_texture(texture, Some(rect), data)
+    }
+
+    pub fn read_texture(&mut self, texture: Texture, rect: Option<Rect>) -> Result<Vec<u8>> {
+        let size = self
+            .query_texture(texture)
+            .ok_or(Error::TextureNotFound(texture))?
+            .size;
+        let rect = try_get_texture_rect(size, rect)?;
+        self.backend.read_texture(texture, Some(rect))
+    }
+
+    pub fn read_screen(&mut self, rect: Option<Rect>) -> Result<Vec<u8>> {
+        let rect = try_get_texture_rect(self.viewport_size, rect)?;
+        self.backend.read_screen(Some(rect))
+    }
+
+    // Keeping a copy of the screen around costs an extra full-screen pass every frame,
+    // so `read_screen` only works after capture has been enabled.
+    pub fn set_screen_capture(&mut self, enabled: bool) {
+        self.backend.set_screen_capture(enabled);
+    }
+
     pub fn unload_texture(&mut self, texture: Texture) {
+        self.cameras.remove(&Some(texture));
+        self.scissors.remove(&Some(texture));
         self.backend.unload_texture(texture);
     }
 
-    pub fn query_texture(&self, texture: Texture) -> Option<TextureQuery> {
+    pub fn query_texture(&self, texture: Texture) -> Option<TextureQuery<'_>> {
         self.backend.query_texture(texture)
     }
 
+    pub fn load_shader(&mut self, options: ShaderLoadOptions) -> Result<Shader> {
+        self.backend.load_shader(options)
+    }
+
+    pub fn unload_shader(&mut self, shader: Shader) {
+        self.backend.unload_shader(shader);
+    }
+
+    pub fn push_transform(&mut self, transform: Transform2D) {
+        let parent = self.transform();
+        self.transforms.push((transform, parent * transform));
+    }
+
+    pub fn pop_transform(&mut self) -> Option<Transform2D> {
+        self.transforms.pop().map(|(transform, _)| transform)
+    }
+
+    pub fn transform(&self) -> Transform2D {
+        self.transforms.last().map(|&(_, transform)| transform).unwrap_or_default()
+    }
+
+    pub fn set_camera(&mut self, target: Option<Texture>, camera: Option<Camera2D>) {
+        match camera {
+            Some(camera) => self.cameras.insert(target, camera),
+            None => self.cameras.remove(&target),
+        };
+    }
+
+    pub fn camera(&self, target: Option<Texture>) -> Option<Camera2D> {
+        self.cameras.get(&target).copied()
+    }
+
+    pub fn set_scissor(&mut self, target: Option<Texture>, scissor: Option<Rect>) {
+        match scissor {
+            Some(scissor) => self.scissors.insert(target, scissor),
+            None => self.scissors.remove(&target),
+        };
+    }
+
+    pub fn scissor(&self, target: Option<Texture>) -> Option<Rect> {
+        self.scissors.get(&target).copied()
+    }
+
+    pub fn world_to_screen(&self, point: Point, target: Option<Texture>) -> Result<Point> {
+        match self.camera(target) {
+            Some(camera) => Ok(camera.world_to_screen(point, self.try_get_target_size(target)?)),
+            None => Ok(point),
+        }
+    }
+
+    pub fn screen_to_world(&self, point: Point, target: Option<Texture>) -> Result<Point> {
+        match self.camera(target) {
+            Some(camera) => Ok(camera.screen_to_world(point, self.try_get_target_size(target)?)),
+            None => Ok(point),
+        }
+    }
+
     pub fn resize_viewport(&mut self, new_size: ViewSize) {
         self.viewport_size = new_size;
         self.backend.resize_viewport(new_size);
@@ -119,43 +476,109 @@ where
         &mut self.backend
     }
 
+    // With post-processing enabled, everything aimed at the swap chain is redirected into
+    // the scene target, and the effect passes are appended after sorting and merging.
+    fn present_commands(&mut self) -> Result<()> {
+        if self.post_process.is_some() {
+            let scene = self.post_processor.scene_target(&mut self.backend, self.viewport_size)?;
+            for target in self.commands.iter_mut().map(DrawCommand::target_mut) {
+                target.get_or_insert(scene);
+            }
+        }
+        if self.layer_sorting {
+            sort::sort_by_layer(&mut self.commands, &mut self.quads, &mut self.layers);
+        }
+        if self.batch_merging {
+            self.merge_stats = merge::merge_batches(&mut self.commands, &mut self.quads, &mut self.layers);
+        }
+        if let Some(post_process) = &self.post_process {
+            self.post_processor.push_passes(
+                &mut self.backend,
+                post_process,
+                &mut self.commands,
+                &mut self.quads,
+                &mut self.layers,
+            )?;
+        }
+        self.backend.present(&self.commands, &self.quads, &self.vertices, &self.indices)
+    }
+
     fn push_command_if_necessary(&mut self, options: TextureBatchOptions) {
-        match self.commands.last_mut() {
-            Some(command) => match command {
-                DrawCommand::DrawTextureBatch(DrawTextureBatchCommand {
-                    texture, target, ..
-                }) if *texture == options.texture && *target == options.target => return,
-                _ => {}
+        let command = DrawTextureBatchCommand {
+            texture: options.texture,
+            target: options.target,
+            blend_mode: options.blend_mode,
+            shader: options.shader,
+            uniforms: options.uniforms.cloned(),
+            sampler: options.sampler,
+            camera: self.camera(options.target),
+            scissor: self.scissor(options.target),
+            range: self.quads.len()..self.quads.len(),
+        };
+
+        if let Some(DrawCommand::DrawTextureBatch(last)) = self.commands.last() {
+            if last.batches_with(&command) {
+                return;
             }
-            _ => {}
         }
 
-        self.commands
-            .push(DrawCommand::DrawTextureBatch(DrawTextureBatchCommand {
-                texture: options.texture,
-                target: options.target,
-                range: self.quads.len()..self.quads.len(),
-            }));
+        self.commands.push(DrawCommand::DrawTextureBatch(command));
+    }
+
+    fn try_get_target_size(&self, target: Option<Texture>) -> Result<ViewSize> {
+        match target {
+            Some(target) => self
+                .query_texture(target)
+                .map(|query| query.size)
+                .ok_or(Error::TargetNotFound(target)),
+            None => Ok(self.viewport_size),
+        }
     }
 
-    fn try_get_batch_view_size(&self, options: TextureBatchOptions) -> Result<ViewSize, String> {
+    fn try_get_batch_view_size(&self, options: TextureBatchOptions) -> Result<ViewSize> {
+        let uniform_textures = options.uniforms.map(UniformBlock::textures).unwrap_or_default();
+        if uniform_textures.len() > MAX_UNIFORM_TEXTURES {
+            return Err(Error::TooManyUniformTextures {
+                max: MAX_UNIFORM_TEXTURES,
+                actual: uniform_textures.len(),
+            });
+        }
+        for &texture in uniform_textures {
+            self.query_texture(texture)
+                .ok_or(Error::TextureNotFound(texture))?;
+        }
         if let Some(target) = options.target {
-            if target == options.texture {
-                return Err(String::from(
-                    "A batch's texture cannot be the same as it's target.",
-                ));
+            if target == options.texture || uniform_textures.contains(&target) {
+                return Err(Error::SelfTargetingBatch(target));
             }
+        }
+        self.try_get_target_view_size(options.target)
+    }
+
+    fn try_get_target_view_size(&self, target: Option<Texture>) -> Result<ViewSize> {
+        if let Some(target) = target {
             let target_query = self
                 .query_texture(target)
-                .ok_or(String::from("Target texture not found"))?;
+                .ok_or(Error::TargetNotFound(target))?;
             if target_query.usage != TextureUsage::RenderTarget {
-                return Err(String::from(
-                    "Target texture is not usable as RenderTarget.",
-                ));
+                return Err(Error::NotARenderTarget(target));
             }
-            return Ok(target_query.size);
+            Ok(target_query.size)
         } else {
-            return Ok(self.viewport_size);
+            Ok(self.viewport_size)
         }
     }
 }
+
+fn try_get_texture_rect(size: ViewSize, rect: Option<Rect>) -> Result<Rect> {
+    let rect = rect.unwrap_or_else(|| size.into());
+    let fits = rect.x >= 0
+        && rect.y >= 0
+        && rect.x as u64 + rect.w as u64 <= size.width as u64
+        && rect.y as u64 + rect.h as u64 <= size.height as u64;
+    if fits {
+        Ok(rect)
+    } else {
+        Err(Error::InvalidTextureRect(rect))
+    }
+}
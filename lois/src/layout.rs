@@ -0,0 +1,460 @@
+use std::ops::Range;
+
+use crate::commons::{FRect, Point};
+
+pub trait TextMetrics {
+    fn line_height(&self) -> f32;
+    fn advance(&self, character: char) -> f32;
+    fn kerning(&self, first: char, second: char) -> f32;
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum HorizontalAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct LayoutOptions {
+    pub bounds: FRect,
+    pub wrap: bool,
+    pub align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+    pub line_spacing: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LayoutGlyph {
+    pub character: char,
+    pub index: usize,
+    pub line: usize,
+    pub rect: FRect,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutLine {
+    pub glyphs: Range<usize>,
+    pub text: Range<usize>,
+    pub rect: FRect,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TextLayout {
+    glyphs: Vec<LayoutGlyph>,
+    lines: Vec<LayoutLine>,
+    bounds: FRect,
+}
+
+struct Line {
+    chars: Vec<(usize, char)>,
+    end: usize,
+    width: f32,
+    breaks_paragraph: bool,
+}
+
+// Running width of a line as characters are appended, both with and without its
+// trailing spaces.
+#[derive(Copy, Clone, Debug, Default)]
+struct LineWidth {
+    total: f32,
+    content: f32,
+    last: Option<char>,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            bounds: FRect::default(),
+            wrap: false,
+            align: HorizontalAlign::default(),
+            vertical_align: VerticalAlign::default(),
+            line_spacing: 1.0,
+        }
+    }
+}
+
+impl LayoutOptions {
+    pub fn new(bounds: FRect) -> Self {
+        Self {
+            bounds,
+            wrap: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_align(self, align: HorizontalAlign) -> Self {
+        Self { align, ..self }
+    }
+
+    pub fn with_vertical_align(self, vertical_align: VerticalAlign) -> Self {
+        Self { vertical_align, ..self }
+    }
+
+    pub fn with_line_spacing(self, line_spacing: f32) -> Self {
+        Self { line_spacing, ..self }
+    }
+}
+
+impl TextLayout {
+    pub fn new<M>(metrics: &M, text: &str, options: LayoutOptions) -> Self
+    where
+        M: TextMetrics + ?Sized,
+    {
+        let LayoutOptions {
+            bounds,
+            wrap,
+            align,
+            vertical_align,
+            line_spacing,
+        } = options;
+        let max_width = if wrap && bounds.w > 0.0 { bounds.w } else { f32::INFINITY };
+        let lines = break_lines(metrics, text, max_width);
+
+        let line_height = metrics.line_height();
+        let line_advance = line_height * line_spacing;
+        let text_width = lines.iter().map(|line| line.width).fold(0.0, f32::max);
+        let text_height = match lines.len() {
+            0 => 0.0,
+            len => (len - 1) as f32 * line_advance + line_height,
+        };
+
+        let box_width = if bounds.w > 0.0 { bounds.w } else { text_width };
+        let box_height = if bounds.h > 0.0 { bounds.h } else { text_height };
+        let top = bounds.y
+            + match vertical_align {
+                VerticalAlign::Top => 0.0,
+                VerticalAlign::Middle => (box_height - text_height) / 2.0,
+                VerticalAlign::Bottom => box_height - text_height,
+            };
+
+        let mut layout = Self::default();
+        for (number, line) in lines.iter().enumerate() {
+            let width = line.width;
+            let y = top + number as f32 * line_advance;
+            let content_len = content(&line.chars).len();
+            let spaces = line.chars[..content_len].iter().filter(|(_, character)| *character == ' ').count();
+            let (x, space_extra) = match align {
+                HorizontalAlign::Left => (0.0, 0.0),
+                HorizontalAlign::Center => ((box_width - width) / 2.0, 0.0),
+                HorizontalAlign::Right => (box_width - width, 0.0),
+                HorizontalAlign::Justify if line.breaks_paragraph || spaces == 0 => (0.0, 0.0),
+                HorizontalAlign::Justify => (0.0, ((box_width - width) / spaces as f32).max(0.0)),
+            };
+
+            let start = layout.glyphs.len();
+            let mut pen = bounds.x + x;
+            let mut previous = None;
+            for (position, &(index, character)) in line.chars.iter().enumerate() {
+                if let Some(previous) = previous {
+                    pen += metrics.kerning(previous, character);
+                }
+                previous = Some(character);
+                let mut advance = metrics.advance(character);
+                if character == ' ' && position < content_len {
+                    advance += space_extra;
+                }
+                layout.glyphs.push(LayoutGlyph {
+                    character,
+                    index,
+                    line: number,
+                    rect: FRect::new(pen, y, advance, line_height),
+                });
+                pen += advance;
+            }
+
+            let line_start = line.chars.first().map(|&(index, _)| index).unwrap_or(line.end);
+            layout.lines.push(LayoutLine {
+                glyphs: start..layout.glyphs.len(),
+                text: line_start..line.end,
+                rect: FRect::new(bounds.x + x, y, width + space_extra * spaces as f32, line_height),
+            });
+        }
+
+        layout.bounds = layout
+            .lines
+            .iter()
+            .map(|line| line.rect)
+            .reduce(FRect::union)
+            .unwrap_or_else(|| FRect::new(bounds.x, top, 0.0, 0.0));
+        layout
+    }
+
+    pub fn measure<M>(metrics: &M, text: &str) -> FRect
+    where
+        M: TextMetrics + ?Sized,
+    {
+        Self::new(metrics, text, LayoutOptions::default()).bounds
+    }
+
+    pub fn glyphs(&self) -> &[LayoutGlyph] {
+        &self.glyphs
+    }
+
+    pub fn lines(&self) -> &[LayoutLine] {
+        &self.lines
+    }
+
+    pub fn bounds(&self) -> FRect {
+        self.bounds
+    }
+
+    // Returns the byte index in the laid out text of the caret closest to `point`.
+    pub fn hit_test(&self, point: Point) -> usize {
+        let line = match self
+            .lines
+            .iter()
+            .position(|line| point.y < line.rect.y + line.rect.h)
+            .or_else(|| self.lines.len().checked_sub(1))
+        {
+            Some(line) => &self.lines[line],
+            None => return 0,
+        };
+        self.glyphs[line.glyphs.clone()]
+            .iter()
+            .find(|glyph| point.x < glyph.rect.x + glyph.rect.w / 2.0)
+            .map(|glyph| glyph.index)
+            .unwrap_or(line.text.end)
+    }
+
+    // Returns a zero-width rect spanning the line height at the caret placed before the
+    // character at byte `index`, or at the end of its line.
+    pub fn caret_rect(&self, index: usize) -> FRect {
+        if let Some(glyph) = self.glyphs.iter().find(|glyph| glyph.index == index) {
+            return FRect::new(glyph.rect.x, glyph.rect.y, 0.0, glyph.rect.h);
+        }
+        let line = self
+            .lines
+            .iter()
+            .find(|line| line.text.contains(&index) || line.text.end == index)
+            .or_else(|| self.lines.last());
+        match line {
+            Some(line) => {
+                let x = self.glyphs[line.glyphs.clone()]
+                    .last()
+                    .map(|glyph| glyph.rect.x + glyph.rect.w)
+                    .unwrap_or(line.rect.x);
+                FRect::new(x, line.rect.y, 0.0, line.rect.h)
+            }
+            None => FRect::new(self.bounds.x, self.bounds.y, 0.0, 0.0),
+        }
+    }
+}
+
+impl LineWidth {
+    fn push<M>(&mut self, metrics: &M, character: char)
+    where
+        M: TextMetrics + ?Sized,
+    {
+        if let Some(last) = self.last {
+            self.total += metrics.kerning(last, character);
+        }
+        self.total += metrics.advance(character);
+        self.last = Some(character);
+        if character != ' ' {
+            self.content = self.total;
+        }
+    }
+}
+
+// Splits the text on newlines, then greedily wraps every paragraph on word boundaries.
+// Words wider than `max_width` on their own are broken between characters.
+fn break_lines<M>(metrics: &M, text: &str, max_width: f32) -> Vec<Line>
+where
+    M: TextMetrics + ?Sized,
+{
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for paragraph in text.split('\n') {
+        let chars: Vec<(usize, char)> = paragraph
+            .char_indices()
+            .map(|(index, character)| (offset + index, character))
+            .collect();
+        let paragraph_end = offset + paragraph.len();
+        offset = paragraph_end + 1;
+
+        let mut line: Vec<(usize, char)> = Vec::new();
+        let mut width = LineWidth::default();
+        let mut rest = &chars[..];
+        while !rest.is_empty() {
+            let word_len = rest.iter().take_while(|(_, character)| *character != ' ').count();
+            let token_len = word_len + rest[word_len..].iter().take_while(|(_, character)| *character == ' ').count();
+            let (token, remaining) = rest.split_at(token_len);
+            rest = remaining;
+
+            let mut candidate = width;
+            for &(_, character) in &token[..word_len] {
+                candidate.push(metrics, character);
+            }
+            if candidate.content <= max_width {
+                for &(_, character) in &token[word_len..] {
+                    candidate.push(metrics, character);
+                }
+                line.extend_from_slice(token);
+                width = candidate;
+                continue;
+            }
+
+            if !line.is_empty() {
+                let end = line.last().map(|&(index, character)| index + character.len_utf8()).unwrap_or(paragraph_end);
+                lines.push(Line {
+                    chars: std::mem::take(&mut line),
+                    end,
+                    width: width.content,
+                    breaks_paragraph: false,
+                });
+                width = LineWidth::default();
+            }
+            for &(index, character) in token.iter() {
+                let previous = width;
+                line.push((index, character));
+                width.push(metrics, character);
+                if line.len() > 1 && character != ' ' && width.content > max_width {
+                    let last = line.pop().unwrap();
+                    lines.push(Line {
+                        chars: std::mem::replace(&mut line, vec![last]),
+                        end: index,
+                        width: previous.content,
+                        breaks_paragraph: false,
+                    });
+                    width = LineWidth::default();
+                    width.push(metrics, character);
+                }
+            }
+        }
+        lines.push(Line {
+            chars: line,
+            end: paragraph_end,
+            width: width.content,
+            breaks_paragraph: true,
+        });
+    }
+    lines
+}
+
+fn content(chars: &[(usize, char)]) -> &[(usize, char)] {
+    let len = chars.len() - chars.iter().rev().take_while(|(_, character)| *character == ' ').count();
+    &chars[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Monospace;
+
+    impl TextMetrics for Monospace {
+        fn line_height(&self) -> f32 {
+            20.0
+        }
+
+        fn advance(&self, _character: char) -> f32 {
+            10.0
+        }
+
+        fn kerning(&self, _first: char, _second: char) -> f32 {
+            0.0
+        }
+    }
+
+    fn layout(text: &str, width: f32, align: HorizontalAlign) -> TextLayout {
+        TextLayout::new(&Monospace, text, LayoutOptions::new(FRect::new(0.0, 0.0, width, 0.0)).with_align(align))
+    }
+
+    fn line_texts(layout: &TextLayout) -> Vec<std::ops::Range<usize>> {
+        layout.lines().iter().map(|line| line.text.clone()).collect()
+    }
+
+    fn glyph_x(layout: &TextLayout, character: char) -> f32 {
+        layout.glyphs().iter().find(|glyph| glyph.character == character).unwrap().rect.x
+    }
+
+    #[test]
+    fn wraps_on_word_boundaries() {
+        let layout = layout("hello world foo", 100.0, HorizontalAlign::Left);
+
+        assert_eq!(line_texts(&layout), vec![0..6, 6..15]);
+        assert_eq!(layout.lines()[0].rect, FRect::new(0.0, 0.0, 50.0, 20.0));
+        assert_eq!(layout.lines()[1].rect, FRect::new(0.0, 20.0, 90.0, 20.0));
+        assert_eq!(layout.bounds(), FRect::new(0.0, 0.0, 90.0, 40.0));
+    }
+
+    #[test]
+    fn breaks_long_words_between_characters() {
+        let layout = layout("abcdefghij", 35.0, HorizontalAlign::Left);
+
+        assert_eq!(line_texts(&layout), vec![0..3, 3..6, 6..9, 9..10]);
+        assert!(layout.glyphs().iter().all(|glyph| glyph.rect.x + glyph.rect.w <= 35.0));
+    }
+
+    #[test]
+    fn keeps_newlines_without_wrapping() {
+        let layout = TextLayout::new(&Monospace, "ab cd\nef", LayoutOptions::default());
+
+        assert_eq!(line_texts(&layout), vec![0..5, 6..8]);
+        assert_eq!(TextLayout::measure(&Monospace, "ab cd\nef"), FRect::new(0.0, 0.0, 50.0, 40.0));
+    }
+
+    #[test]
+    fn aligns_lines_left_center_and_right() {
+        let left = layout("ab cd ef", 60.0, HorizontalAlign::Left);
+        assert_eq!((glyph_x(&left, 'a'), glyph_x(&left, 'e')), (0.0, 0.0));
+
+        let center = layout("ab cd ef", 60.0, HorizontalAlign::Center);
+        assert_eq!((glyph_x(&center, 'a'), glyph_x(&center, 'e')), (5.0, 20.0));
+
+        let right = layout("ab cd ef", 60.0, HorizontalAlign::Right);
+        assert_eq!((glyph_x(&right, 'a'), glyph_x(&right, 'e')), (10.0, 40.0));
+        assert_eq!(right.lines()[1].rect, FRect::new(40.0, 20.0, 20.0, 20.0));
+    }
+
+    #[test]
+    fn justifies_all_but_the_last_line() {
+        let layout = layout("ab cd ef", 60.0, HorizontalAlign::Justify);
+
+        assert_eq!(glyph_x(&layout, 'c'), 40.0);
+        assert_eq!(glyph_x(&layout, 'd'), 50.0);
+        assert_eq!(layout.lines()[0].rect, FRect::new(0.0, 0.0, 60.0, 20.0));
+        assert_eq!(glyph_x(&layout, 'e'), 0.0);
+        assert_eq!(layout.lines()[1].rect, FRect::new(0.0, 20.0, 20.0, 20.0));
+    }
+
+    #[test]
+    fn does_not_justify_lines_ending_a_paragraph() {
+        let layout = layout("ab c\nde f", 60.0, HorizontalAlign::Justify);
+
+        assert_eq!(glyph_x(&layout, 'c'), 30.0);
+        assert_eq!(glyph_x(&layout, 'f'), 30.0);
+    }
+
+    #[test]
+    fn hit_tests_the_closest_caret() {
+        let layout = TextLayout::new(&Monospace, "abc\nde", LayoutOptions::default());
+
+        assert_eq!(layout.hit_test(Point::new(14.0, 5.0)), 1);
+        assert_eq!(layout.hit_test(Point::new(16.0, 5.0)), 2);
+        assert_eq!(layout.hit_test(Point::new(100.0, 5.0)), 3);
+        assert_eq!(layout.hit_test(Point::new(-5.0, 25.0)), 4);
+        assert_eq!(layout.hit_test(Point::new(100.0, 100.0)), 6);
+    }
+
+    #[test]
+    fn places_carets_before_characters_and_at_line_ends() {
+        let layout = TextLayout::new(&Monospace, "abc\nde", LayoutOptions::default());
+
+        assert_eq!(layout.caret_rect(1), FRect::new(10.0, 0.0, 0.0, 20.0));
+        assert_eq!(layout.caret_rect(3), FRect::new(30.0, 0.0, 0.0, 20.0));
+        assert_eq!(layout.caret_rect(4), FRect::new(0.0, 20.0, 0.0, 20.0));
+        assert_eq!(layout.caret_rect(6), FRect::new(20.0, 20.0, 0.0, 20.0));
+    }
+}
@@ -1,6 +1,20 @@
-pub mod quad;
+pub mod animation;
+pub mod atlas;
 pub mod backend;
+pub mod batch;
+pub mod camera;
 pub mod commons;
+pub mod error;
+pub mod font;
 pub mod graphics;
+pub mod layout;
+pub mod merge;
+pub mod mesh;
+pub mod postprocess;
+pub mod quad;
+pub mod shader;
+pub mod shape;
+mod sort;
+pub mod text;
 pub mod texture;
-pub mod batch;
\ No newline at end of file
+pub mod transform;
\ No newline at end of file
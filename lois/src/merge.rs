@@ -0,0 +1,378 @@
+use crate::{
+    commons::FRect,
+    graphics::{DrawCommand, DrawTextureBatchCommand},
+    quad::Quad,
+    texture::Texture,
+};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeStats {
+    pub commands_before: usize,
+    pub commands_after: usize,
+}
+
+impl MergeStats {
+    pub fn saved(&self) -> usize {
+        self.commands_before - self.commands_after
+    }
+}
+
+enum Merged<'a> {
+    Batch {
+        command: &'a DrawTextureBatchCommand,
+        quads: Vec<usize>,
+        bounds: FRect,
+    },
+    Other(&'a DrawCommand),
+}
+
+// Moves each batch back onto the closest earlier batch sharing its state, as long as
+// every command in between neither touches the same textures nor draws quads that
+// overlap the ones being moved.
+pub(crate) fn merge_batches(
+    commands: &mut Vec<DrawCommand>,
+    quads: &mut Vec<Quad>,
+    layers: &mut Vec<i32>,
+) -> MergeStats {
+    let unmerged = std::mem::take(commands);
+    let bounds: Vec<FRect> = quads.iter().map(Quad::bounds).collect();
+    let mut merged: Vec<Merged> = Vec::with_capacity(unmerged.len());
+
+    for command in unmerged.iter() {
+        let batch = match command {
+            DrawCommand::DrawTextureBatch(batch) if !batch.range.is_empty() => batch,
+            DrawCommand::DrawTextureBatch(_) => continue,
+            command => {
+                merged.push(Merged::Other(command));
+                continue;
+            }
+        };
+
+        let batch_bounds = batch
+            .range
+            .clone()
+            .map(|index| bounds[index])
+            .reduce(FRect::union)
+            .unwrap_or_default();
+
+        let mut destination = None;
+        for (position, entry) in merged.iter().enumerate().rev() {
+            match entry {
+                Merged::Batch { command, .. } if command.batches_with(batch) => {
+                    destination = Some(position);
+                    break;
+                }
+                Merged::Batch {
+                    command,
+                    quads: entry_quads,
+                    bounds: entry_bounds,
+                } => {
+                    if conflicts(command, batch) {
+                        break;
+                    }
+                    if command.target == batch.target
+                        && (command.camera != batch.camera
+                            || overlaps(entry_quads, *entry_bounds, batch, batch_bounds, &bounds))
+                    {
+                        break;
+                    }
+                }
+                Merged::Other(_) => break,
+            }
+        }
+
+        match destination.map(|position| &mut merged[position]) {
+            Some(Merged::Batch {
+                quads: entry_quads,
+                bounds: entry_bounds,
+                ..
+            }) => {
+                entry_quads.extend(batch.range.clone());
+                *entry_bounds = entry_bounds.union(batch_bounds);
+            }
+            _ => merged.push(Merged::Batch {
+                command: batch,
+                quads: batch.range.clone().collect(),
+                bounds: batch_bounds,
+            }),
+        }
+    }
+
+    let mut merged_quads = Vec::with_capacity(quads.len());
+    let mut merged_layers = Vec::with_capacity(layers.len());
+    for entry in merged {
+        match entry {
+            Merged::Batch {
+                command,
+                quads: entry_quads,
+                ..
+            } => {
+                let start = merged_quads.len();
+                for index in entry_quads {
+                    merged_quads.push(quads[index]);
+                    merged_layers.push(layers[index]);
+                }
+                commands.push(DrawCommand::DrawTextureBatch(DrawTextureBatchCommand {
+                    range: start..merged_quads.len(),
+                    ..command.clone()
+                }));
+            }
+            Merged::Other(command) => commands.push(command.clone()),
+        }
+    }
+
+    *quads = merged_quads;
+    *layers = merged_layers;
+
+    MergeStats {
+        commands_before: unmerged.len(),
+        commands_after: commands.len(),
+    }
+}
+
+fn conflicts(lhs: &DrawTextureBatchCommand, rhs: &DrawTextureBatchCommand) -> bool {
+    let reads = |command: &DrawTextureBatchCommand, texture: Option<Texture>| match texture {
+        Some(texture) => {
+            command.texture == texture
+                || command
+                    .uniforms
+                    .as_ref()
+                    .is_some_and(|uniforms| uniforms.textures().contains(&texture))
+        }
+        None => false,
+    };
+    reads(rhs, lhs.target) || reads(lhs, rhs.target)
+}
+
+fn overlaps(
+    entry_quads: &[usize],
+    entry_bounds: FRect,
+    batch: &DrawTextureBatchCommand,
+    batch_bounds: FRect,
+    bounds: &[FRect],
+) -> bool {
+    if !entry_bounds.intersects(batch_bounds) {
+        return false;
+    }
+    entry_quads.iter().any(|&lhs| {
+        bounds[lhs].intersects(batch_bounds)
+            && batch.range.clone().any(|rhs| bounds[lhs].intersects(bounds[rhs]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        camera::Camera2D,
+        commons::{Color, Point, ViewSize},
+        graphics::{ClearCommand, DrawOptions},
+        shader::UniformBlock,
+        transform::Transform2D,
+    };
+
+    #[derive(Default)]
+    struct Frame {
+        commands: Vec<DrawCommand>,
+        quads: Vec<Quad>,
+        layers: Vec<i32>,
+    }
+
+    impl Frame {
+        fn batch(&mut self, texture: u32, target: Option<u32>, positions: &[(f32, f32)]) -> &mut DrawTextureBatchCommand {
+            let start = self.quads.len();
+            for &(x, y) in positions {
+                let options = DrawOptions {
+                    dest_rect: Some(FRect::new(x, y, 10.0, 10.0)),
+                    ..Default::default()
+                };
+                self.quads.push(Quad::new(ViewSize::new(100, 100), ViewSize::new(10, 10), Transform2D::IDENTITY, options));
+                self.layers.push(self.layers.len() as i32);
+            }
+            self.commands.push(DrawCommand::DrawTextureBatch(DrawTextureBatchCommand {
+                texture: Texture::new(texture),
+                target: target.map(Texture::new),
+                blend_mode: Default::default(),
+                shader: None,
+                uniforms: None,
+                sampler: None,
+                camera: None,
+                scissor: None,
+                range: start..self.quads.len(),
+            }));
+            match self.commands.last_mut() {
+                Some(DrawCommand::DrawTextureBatch(command)) => command,
+                _ => unreachable!(),
+            }
+        }
+
+        fn clear(&mut self, target: Option<u32>) {
+            self.commands.push(DrawCommand::Clear(ClearCommand {
+                target: target.map(Texture::new),
+                color: Color::BLACK,
+            }));
+        }
+
+        fn merge(&mut self) -> MergeStats {
+            merge_batches(&mut self.commands, &mut self.quads, &mut self.layers)
+        }
+
+        // Texture id and quad count of every batch, with `None` standing for other commands.
+        fn summary(&self) -> Vec<Option<(u32, usize)>> {
+            self.commands
+                .iter()
+                .map(|command| match command {
+                    DrawCommand::DrawTextureBatch(command) => Some((command.texture.id(), command.range.len())),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn merges_batches_around_disjoint_draws() {
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[(0.0, 0.0)]);
+        frame.batch(2, None, &[(20.0, 0.0)]);
+        frame.batch(1, None, &[(40.0, 0.0)]);
+
+        let stats = frame.merge();
+
+        assert_eq!(frame.summary(), vec![Some((1, 2)), Some((2, 1))]);
+        assert_eq!(frame.layers, vec![0, 2, 1]);
+        assert_eq!(frame.quads[1].bounds(), FRect::new(40.0, 0.0, 10.0, 10.0));
+        assert_eq!(
+            stats,
+            MergeStats {
+                commands_before: 3,
+                commands_after: 2,
+            }
+        );
+        assert_eq!(stats.saved(), 1);
+    }
+
+    #[test]
+    fn overlapping_draws_block_merging() {
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[(0.0, 0.0)]);
+        frame.batch(2, None, &[(5.0, 5.0)]);
+        frame.batch(1, None, &[(10.0, 10.0)]);
+
+        let stats = frame.merge();
+
+        assert_eq!(frame.summary(), vec![Some((1, 1)), Some((2, 1)), Some((1, 1))]);
+        assert_eq!(stats.saved(), 0);
+    }
+
+    #[test]
+    fn overlap_is_checked_per_quad() {
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[(0.0, 0.0)]);
+        frame.batch(2, None, &[(20.0, 0.0), (0.0, 20.0)]);
+        frame.batch(1, None, &[(20.0, 20.0)]);
+
+        frame.merge();
+
+        assert_eq!(frame.summary(), vec![Some((1, 2)), Some((2, 2))]);
+    }
+
+    #[test]
+    fn draws_on_other_targets_never_overlap() {
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[(0.0, 0.0)]);
+        frame.batch(2, Some(5), &[(0.0, 0.0)]);
+        frame.batch(1, None, &[(0.0, 0.0)]);
+
+        frame.merge();
+
+        assert_eq!(frame.summary(), vec![Some((1, 2)), Some((2, 1))]);
+    }
+
+    #[test]
+    fn sampling_a_target_written_in_between_blocks_merging() {
+        let mut frame = Frame::default();
+        frame.batch(5, None, &[(0.0, 0.0)]);
+        frame.batch(2, Some(5), &[(0.0, 0.0)]);
+        frame.batch(5, None, &[(40.0, 40.0)]);
+
+        let stats = frame.merge();
+
+        assert_eq!(frame.summary(), vec![Some((5, 1)), Some((2, 1)), Some((5, 1))]);
+        assert_eq!(stats.saved(), 0);
+    }
+
+    #[test]
+    fn writing_a_target_sampled_in_between_blocks_merging() {
+        let mut frame = Frame::default();
+        frame.batch(1, Some(5), &[(0.0, 0.0)]);
+        frame.batch(5, None, &[(0.0, 0.0)]);
+        frame.batch(1, Some(5), &[(40.0, 40.0)]);
+
+        frame.merge();
+
+        assert_eq!(frame.summary(), vec![Some((1, 1)), Some((5, 1)), Some((1, 1))]);
+    }
+
+    #[test]
+    fn sampling_a_target_through_uniforms_blocks_merging() {
+        let mut frame = Frame::default();
+        frame.batch(1, Some(5), &[(0.0, 0.0)]);
+        frame.batch(2, None, &[(0.0, 0.0)]).uniforms = Some(UniformBlock::new().with_texture(Texture::new(5)));
+        frame.batch(1, Some(5), &[(40.0, 40.0)]);
+
+        frame.merge();
+
+        assert_eq!(frame.summary(), vec![Some((1, 1)), Some((2, 1)), Some((1, 1))]);
+    }
+
+    #[test]
+    fn clears_are_barriers() {
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[(0.0, 0.0)]);
+        frame.clear(Some(5));
+        frame.batch(1, None, &[(40.0, 40.0)]);
+
+        let stats = frame.merge();
+
+        assert_eq!(frame.summary(), vec![Some((1, 1)), None, Some((1, 1))]);
+        assert_eq!(stats.saved(), 0);
+    }
+
+    #[test]
+    fn camera_changes_on_the_same_target_are_barriers() {
+        let camera = Some(Camera2D::new(Point::new(10.0, 0.0)));
+
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[(0.0, 0.0)]);
+        frame.batch(2, None, &[(40.0, 40.0)]).camera = camera;
+        frame.batch(1, None, &[(80.0, 80.0)]);
+        frame.merge();
+        assert_eq!(frame.summary(), vec![Some((1, 1)), Some((2, 1)), Some((1, 1))]);
+
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[(0.0, 0.0)]);
+        frame.batch(2, Some(5), &[(40.0, 40.0)]).camera = camera;
+        frame.batch(1, None, &[(80.0, 80.0)]);
+        frame.merge();
+        assert_eq!(frame.summary(), vec![Some((1, 2)), Some((2, 1))]);
+    }
+
+    #[test]
+    fn empty_batches_are_dropped() {
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[(0.0, 0.0)]);
+        frame.batch(2, None, &[]);
+        frame.batch(3, None, &[(20.0, 0.0)]);
+
+        let stats = frame.merge();
+
+        assert_eq!(frame.summary(), vec![Some((1, 1)), Some((3, 1))]);
+        assert_eq!(
+            stats,
+            MergeStats {
+                commands_before: 3,
+                commands_after: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    batch::BlendMode,
+    commons::{Color, Point},
+    texture::Texture,
+};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MeshVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum MeshIndices<'a> {
+    U16(&'a [u16]),
+    U32(&'a [u32]),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MeshOptions {
+    pub texture: Texture,
+    pub target: Option<Texture>,
+    pub blend_mode: BlendMode,
+}
+
+impl MeshVertex {
+    #[inline(always)]
+    pub fn new(position: Point, uv: Point, color: Color) -> Self {
+        Self {
+            position: position.into(),
+            uv: uv.into(),
+            color: color.normalized(),
+        }
+    }
+}
+
+impl MeshIndices<'_> {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U16(indices) => indices.len(),
+            Self::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        match self {
+            Self::U16(indices) => Box::new(indices.iter().map(|&index| index as u32)),
+            Self::U32(indices) => Box::new(indices.iter().copied()),
+        }
+    }
+}
+
+impl<'a> From<&'a [u16]> for MeshIndices<'a> {
+    fn from(indices: &'a [u16]) -> Self {
+        Self::U16(indices)
+    }
+}
+
+impl<'a> From<&'a [u32]> for MeshIndices<'a> {
+    fn from(indices: &'a [u32]) -> Self {
+        Self::U32(indices)
+    }
+}
+
+impl MeshOptions {
+    pub fn new(texture: Texture, target: Option<Texture>) -> Self {
+        Self {
+            texture,
+            target,
+            blend_mode: BlendMode::default(),
+        }
+    }
+
+    pub fn with_blend_mode(self, blend_mode: BlendMode) -> Self {
+        Self { blend_mode, ..self }
+    }
+}
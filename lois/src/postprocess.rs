@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+
+use crate::{
+    backend::Backend,
+    batch::BlendMode,
+    commons::{Color, ViewSize},
+    error::Result,
+    graphics::{DrawCommand, DrawOptions, DrawTextureBatchCommand},
+    quad::Quad,
+    shader::{Shader, UniformBlock},
+    texture::{SamplerOptions, Texture, TextureLoadOptions, TextureUsage},
+    transform::Transform2D,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Effect {
+    Blur {
+        radius: f32,
+    },
+    Bloom {
+        threshold: f32,
+        radius: f32,
+        intensity: f32,
+    },
+    Vignette {
+        radius: f32,
+        softness: f32,
+        intensity: f32,
+    },
+    ColorGrading {
+        brightness: f32,
+        contrast: f32,
+        saturation: f32,
+        tint: Color,
+    },
+    Crt {
+        curvature: f32,
+        scanline_intensity: f32,
+    },
+    Custom {
+        shader: Shader,
+        uniforms: UniformBlock,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PostProcess {
+    effects: Vec<Effect>,
+}
+
+#[derive(Default)]
+pub(crate) struct PostProcessor {
+    targets: Option<Targets>,
+    shaders: HashMap<BuiltinShader, Shader>,
+}
+
+struct Targets {
+    size: ViewSize,
+    ping: Texture,
+    pong: Texture,
+    bloom: [Texture; 2],
+}
+
+// Fragment shaders of the built-in effects, provided by the backend through
+// `Backend::load_builtin_shader`. Each pass samples its source texture like any other batch,
+// and receives the uniform values listed below, in order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BuiltinShader {
+    // direction: vec2, texel_size: vec2, radius: float
+    Blur,
+    // threshold: float
+    Threshold,
+    // intensity: float
+    Intensity,
+    // radius: float, softness: float, intensity: float
+    Vignette,
+    // tint: vec4, brightness: float, contrast: float, saturation: float
+    ColorGrading,
+    // resolution: vec2, curvature: float, scanline_intensity: float
+    Crt,
+}
+
+struct Passes<'a> {
+    commands: &'a mut Vec<DrawCommand>,
+    quads: &'a mut Vec<Quad>,
+    layers: &'a mut Vec<i32>,
+    size: ViewSize,
+}
+
+impl PostProcess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_effect(mut self, effect: Effect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    pub fn push(&mut self, effect: Effect) {
+        self.effects.push(effect);
+    }
+
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+}
+
+impl PostProcessor {
+    // Returns the render target the scene is drawn into, (re)creating the ping-pong
+    // targets whenever the viewport size changed since the last frame.
+    pub(crate) fn scene_target<B>(&mut self, backend: &mut B, size: ViewSize) -> Result<Texture>
+    where
+        B: Backend,
+    {
+        match &self.targets {
+            Some(targets) if targets.size == size => return Ok(targets.ping),
+            _ => self.unload_targets(backend),
+        }
+        let names = ["post_process_ping", "post_process_pong", "post_process_bloom_a", "post_process_bloom_b"];
+        let mut textures = Vec::with_capacity(names.len());
+        for name in names {
+            let texture = backend.load_texture(TextureLoadOptions {
+                name: Some(name),
+                size,
+                usage: TextureUsage::RenderTarget,
+                sampler: SamplerOptions::linear(),
+                ..Default::default()
+            });
+            match texture {
+                Ok(texture) => textures.push(texture),
+                Err(err) => {
+                    for texture in textures {
+                        backend.unload_texture(texture);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        let targets = Targets {
+            size,
+            ping: textures[0],
+            pong: textures[1],
+            bloom: [textures[2], textures[3]],
+        };
+        let ping = targets.ping;
+        self.targets = Some(targets);
+        Ok(ping)
+    }
+
+    // Appends the passes of every effect, ping-ponging between the scene targets. The last
+    // effect renders straight to the swap chain.
+    pub(crate) fn push_passes<B>(
+        &mut self,
+        backend: &mut B,
+        post_process: &PostProcess,
+        commands: &mut Vec<DrawCommand>,
+        quads: &mut Vec<Quad>,
+        layers: &mut Vec<i32>,
+    ) -> Result<()>
+    where
+        B: Backend,
+    {
+        let (size, mut source, mut spare, bloom) = match &self.targets {
+            Some(targets) => (targets.size, targets.ping, targets.pong, targets.bloom),
+            None => return Ok(()),
+        };
+        let mut passes = Passes {
+            commands,
+            quads,
+            layers,
+            size,
+        };
+        if post_process.effects.is_empty() {
+            passes.copy(source, None, BlendMode::Replace);
+            return Ok(());
+        }
+
+        let texel_size = [1.0 / size.width.max(1) as f32, 1.0 / size.height.max(1) as f32];
+        let blur = |direction: [f32; 2], radius: f32| {
+            UniformBlock::new()
+                .with_vec2(direction)
+                .with_vec2(texel_size)
+                .with_float(radius)
+        };
+        for (index, effect) in post_process.effects.iter().enumerate() {
+            let output = if index + 1 == post_process.effects.len() {
+                None
+            } else {
+                Some(spare)
+            };
+            match effect {
+                Effect::Blur { radius } => {
+                    let shader = self.builtin(backend, BuiltinShader::Blur)?;
+                    passes.effect(source, Some(bloom[0]), shader, blur([1.0, 0.0], *radius));
+                    passes.effect(bloom[0], output, shader, blur([0.0, 1.0], *radius));
+                }
+                Effect::Bloom {
+                    threshold,
+                    radius,
+                    intensity,
+                } => {
+                    let threshold_shader = self.builtin(backend, BuiltinShader::Threshold)?;
+                    let blur_shader = self.builtin(backend, BuiltinShader::Blur)?;
+                    let intensity_shader = self.builtin(backend, BuiltinShader::Intensity)?;
+                    passes.effect(source, Some(bloom[0]), threshold_shader, UniformBlock::new().with_float(*threshold));
+                    passes.effect(bloom[0], Some(bloom[1]), blur_shader, blur([1.0, 0.0], *radius));
+                    passes.effect(bloom[1], Some(bloom[0]), blur_shader, blur([0.0, 1.0], *radius));
+                    passes.copy(source, output, BlendMode::Replace);
+                    passes.push(
+                        bloom[0],
+                        output,
+                        BlendMode::Additive,
+                        Some(intensity_shader),
+                        Some(UniformBlock::new().with_float(*intensity)),
+                    );
+                }
+                Effect::Vignette {
+                    radius,
+                    softness,
+                    intensity,
+                } => {
+                    let shader = self.builtin(backend, BuiltinShader::Vignette)?;
+                    let uniforms = UniformBlock::new()
+                        .with_float(*radius)
+                        .with_float(*softness)
+                        .with_float(*intensity);
+                    passes.effect(source, output, shader, uniforms);
+                }
+                Effect::ColorGrading {
+                    brightness,
+                    contrast,
+                    saturation,
+                    tint,
+                } => {
+                    let shader = self.builtin(backend, BuiltinShader::ColorGrading)?;
+                    let uniforms = UniformBlock::new()
+                        .with_color(*tint)
+                        .with_float(*brightness)
+                        .with_float(*contrast)
+                        .with_float(*saturation);
+                    passes.effect(source, output, shader, uniforms);
+                }
+                Effect::Crt {
+                    curvature,
+                    scanline_intensity,
+                } => {
+                    let shader = self.builtin(backend, BuiltinShader::Crt)?;
+                    let uniforms = UniformBlock::new()
+                        .with_vec2([size.width as f32, size.height as f32])
+                        .with_float(*curvature)
+                        .with_float(*scanline_intensity);
+                    passes.effect(source, output, shader, uniforms);
+                }
+                Effect::Custom { shader, uniforms } => {
+                    passes.effect(source, output, *shader, uniforms.clone());
+                }
+            }
+            std::mem::swap(&mut source, &mut spare);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn unload<B>(&mut self, backend: &mut B)
+    where
+        B: Backend,
+    {
+        self.unload_targets(backend);
+        for (_, shader) in self.shaders.drain() {
+            backend.unload_shader(shader);
+        }
+    }
+
+    fn unload_targets<B>(&mut self, backend: &mut B)
+    where
+        B: Backend,
+    {
+        if let Some(targets) = self.targets.take() {
+            let [bloom_a, bloom_b] = targets.bloom;
+            for texture in [targets.ping, targets.pong, bloom_a, bloom_b] {
+                backend.unload_texture(texture);
+            }
+        }
+    }
+
+    fn builtin<B>(&mut self, backend: &mut B, builtin: BuiltinShader) -> Result<Shader>
+    where
+        B: Backend,
+    {
+        if let Some(&shader) = self.shaders.get(&builtin) {
+            return Ok(shader);
+        }
+        let shader = backend.load_builtin_shader(builtin)?;
+        self.shaders.insert(builtin, shader);
+        Ok(shader)
+    }
+}
+
+impl<'a> Passes<'a> {
+    fn effect(&mut self, texture: Texture, target: Option<Texture>, shader: Shader, uniforms: UniformBlock) {
+        self.push(texture, target, BlendMode::Replace, Some(shader), Some(uniforms));
+    }
+
+    fn copy(&mut self, texture: Texture, target: Option<Texture>, blend_mode: BlendMode) {
+        self.push(texture, target, blend_mode, None, None);
+    }
+
+    fn push(
+        &mut self,
+        texture: Texture,
+        target: Option<Texture>,
+        blend_mode: BlendMode,
+        shader: Option<Shader>,
+        uniforms: Option<UniformBlock>,
+    ) {
+        let start = self.quads.len();
+        self.quads.push(Quad::new(self.size, self.size, Transform2D::IDENTITY, DrawOptions::default()));
+        self.layers.push(0);
+        self.commands.push(DrawCommand::DrawTextureBatch(DrawTextureBatchCommand {
+            texture,
+            target,
+            blend_mode,
+            shader,
+            uniforms,
+            sampler: None,
+            camera: None,
+            scissor: None,
+            range: start..self.quads.len(),
+        }));
+    }
+}
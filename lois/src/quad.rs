@@ -1,37 +1,52 @@
-use crate::commons::{Point, Rect, ViewSize};
+use crate::{batch::BlendMode, commons::{FRect, ViewSize}, graphics::DrawOptions, transform::Transform2D};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct Quad {
     transform: [[f32; 4]; 4],
     src_rect: [f32; 4],
+    color: [f32; 4],
 }
 
 impl Quad {
     pub fn new(
         target_size: ViewSize,
         texture_size: ViewSize,
-        src_rect: Option<Rect>,
-        dest_rect: Option<Rect>,
-        rotation_center: Option<Point>,
-        rotation_angle: f32,
+        parent: Transform2D,
+        options: DrawOptions,
     ) -> Self {
         use glam::*;
 
+        let DrawOptions {
+            src_rect,
+            dest_rect,
+            rotation_center,
+            rotation_angle,
+            color,
+            flip_x,
+            flip_y,
+            ..
+        } = options;
+
         let ViewSize { width: texture_width, height: texture_height } = texture_size;
 
-        let src_rect = src_rect
-            .and_then(|r| Some(r.normalized(texture_width as f32, texture_height as f32)))
+        let mut src_rect = src_rect
+            .map(|r| r.normalized(texture_width as f32, texture_height as f32))
             .unwrap_or([0.0, 0.0, 1.0, 1.0]);
 
+        if flip_x {
+            src_rect[0] += src_rect[2];
+            src_rect[2] = -src_rect[2];
+        }
+
+        if flip_y {
+            src_rect[1] += src_rect[3];
+            src_rect[3] = -src_rect[3];
+        }
+
         let dest_rect = dest_rect.unwrap_or(target_size.into());
 
-        let rotation_center: [f32; 2] = rotation_center
-            .unwrap_or(Point::new(
-                dest_rect.x as f32 + dest_rect.w as f32 / 2.0,
-                dest_rect.y as f32 + dest_rect.h as f32 / 2.0,
-            ))
-            .into();
+        let rotation_center: [f32; 2] = rotation_center.unwrap_or(dest_rect.center()).into();
 
         let dest_rect: [f32; 4] = dest_rect.into();
 
@@ -44,12 +59,46 @@ impl Quad {
             let rotation = Mat4::from_translation(Vec3::new(dx, dy, 0.0))
                 * Mat4::from_rotation_z(rotation_angle)
                 * Mat4::from_translation(Vec3::new(-dx, -dy, 0.0));
-            position * rotation * scale
+            parent.to_mat4() * position * rotation * scale
         };
 
         Quad {
             src_rect,
             transform: transform.to_cols_array_2d(),
+            color: color.normalized(),
+        }
+    }
+
+    pub(crate) fn with_blend_mode(self, blend_mode: BlendMode) -> Self {
+        Quad {
+            color: blend_mode.tint(self.color),
+            ..self
+        }
+    }
+
+    // Source rect in normalized texture coordinates.
+    #[cfg(test)]
+    pub(crate) fn src_rect(&self) -> [f32; 4] {
+        self.src_rect
+    }
+
+    pub fn bounds(&self) -> FRect {
+        let [x_axis, y_axis, _, translation] = self.transform;
+        let corner = |u: f32, v: f32| {
+            (
+                x_axis[0] * u + y_axis[0] * v + translation[0],
+                x_axis[1] * u + y_axis[1] * v + translation[1],
+            )
+        };
+        let corners = [corner(0.0, 0.0), corner(1.0, 0.0), corner(1.0, 1.0), corner(0.0, 1.0)];
+        let (mut left, mut top) = corners[0];
+        let (mut right, mut bottom) = corners[0];
+        for &(x, y) in &corners[1..] {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
         }
+        FRect::new(left, top, right - left, bottom - top)
     }
 }
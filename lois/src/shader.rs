@@ -0,0 +1,226 @@
+use crate::{commons::Color, texture::Texture};
+
+// Uniform blocks are bound at set 2: the values as a std140 uniform buffer at binding 0,
+// then each texture at binding `1 + 2 * i` with its sampler at binding `2 + 2 * i`.
+pub const MAX_UNIFORM_TEXTURES: usize = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Shader(u32);
+
+#[derive(Copy, Clone, Debug)]
+pub enum ShaderSource<'a> {
+    Wgsl(&'a str),
+    Glsl(&'a str),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum UniformValue {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    // Column-major, like GLSL's mat4.
+    Mat4([[f32; 4]; 4]),
+    Color(Color),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UniformBlock {
+    values: Vec<UniformValue>,
+    textures: Vec<Texture>,
+}
+
+// Custom shaders are used with the layout of the built-in ones:
+// - set 0: the batch texture at binding 0 and its sampler at binding 1;
+// - set 1: the view projection as a `mat4` uniform block at binding 0;
+// - set 2: the batch's uniform block, laid out as described above.
+// Fragment shaders receive the texture coordinates at location 0 and the tint at location 1,
+// which a custom vertex shader has to output from the quad vertex at location 0, its transform
+// columns at locations 1 to 4, its source rect at location 5 and its color at location 6.
+// Sources that fail to compile or do not match this layout are rejected with `InvalidShader`.
+#[derive(Copy, Clone, Debug)]
+pub struct ShaderLoadOptions<'a> {
+    pub name: Option<&'a str>,
+    pub vertex: Option<ShaderSource<'a>>,
+    pub fragment: ShaderSource<'a>,
+}
+
+impl Shader {
+    pub fn new(id: u32) -> Self {
+        Self(id)
+    }
+
+    pub fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+impl<'a> ShaderLoadOptions<'a> {
+    pub fn new(fragment: ShaderSource<'a>) -> Self {
+        Self {
+            name: None,
+            vertex: None,
+            fragment,
+        }
+    }
+
+    pub fn with_name(self, name: &'a str) -> Self {
+        Self {
+            name: Some(name),
+            ..self
+        }
+    }
+
+    pub fn with_vertex(self, vertex: ShaderSource<'a>) -> Self {
+        Self {
+            vertex: Some(vertex),
+            ..self
+        }
+    }
+}
+
+impl UniformValue {
+    fn alignment(&self) -> usize {
+        match self {
+            Self::Float(_) => 4,
+            Self::Vec2(_) => 8,
+            Self::Vec3(_) | Self::Vec4(_) | Self::Mat4(_) | Self::Color(_) => 16,
+        }
+    }
+
+    fn components(&self) -> Vec<f32> {
+        match *self {
+            Self::Float(value) => vec![value],
+            Self::Vec2(value) => value.to_vec(),
+            Self::Vec3(value) => value.to_vec(),
+            Self::Vec4(value) => value.to_vec(),
+            Self::Mat4(value) => value.iter().flatten().copied().collect(),
+            Self::Color(color) => color.normalized().to_vec(),
+        }
+    }
+}
+
+impl UniformBlock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_float(self, value: f32) -> Self {
+        self.with_value(UniformValue::Float(value))
+    }
+
+    pub fn with_vec2(self, value: [f32; 2]) -> Self {
+        self.with_value(UniformValue::Vec2(value))
+    }
+
+    pub fn with_vec3(self, value: [f32; 3]) -> Self {
+        self.with_value(UniformValue::Vec3(value))
+    }
+
+    pub fn with_vec4(self, value: [f32; 4]) -> Self {
+        self.with_value(UniformValue::Vec4(value))
+    }
+
+    pub fn with_mat4(self, value: [[f32; 4]; 4]) -> Self {
+        self.with_value(UniformValue::Mat4(value))
+    }
+
+    pub fn with_color(self, color: Color) -> Self {
+        self.with_value(UniformValue::Color(color))
+    }
+
+    pub fn with_value(mut self, value: UniformValue) -> Self {
+        self.values.push(value);
+        self
+    }
+
+    pub fn with_texture(mut self, texture: Texture) -> Self {
+        self.textures.push(texture);
+        self
+    }
+
+    pub fn values(&self) -> &[UniformValue] {
+        &self.values
+    }
+
+    pub fn textures(&self) -> &[Texture] {
+        &self.textures
+    }
+
+    // Packs the values following std140 rules, padding the block to a multiple of 16 bytes.
+    pub fn to_std140_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for value in self.values.iter() {
+            let alignment = value.alignment();
+            bytes.resize(bytes.len().div_ceil(alignment) * alignment, 0);
+            for component in value.components() {
+                bytes.extend_from_slice(&component.to_le_bytes());
+            }
+        }
+        bytes.resize(bytes.len().div_ceil(16).max(1) * 16, 0);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floats(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect()
+    }
+
+    #[test]
+    fn empty_block_is_padded_to_16_bytes() {
+        assert_eq!(UniformBlock::new().to_std140_bytes(), vec![0; 16]);
+    }
+
+    #[test]
+    fn vec2_is_aligned_to_8_bytes() {
+        let bytes = UniformBlock::new().with_float(1.0).with_vec2([2.0, 3.0]).to_std140_bytes();
+        assert_eq!(floats(&bytes), vec![1.0, 0.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn vec3_is_aligned_to_16_bytes_and_leaves_room_for_a_float() {
+        let bytes = UniformBlock::new()
+            .with_float(1.0)
+            .with_vec3([2.0, 3.0, 4.0])
+            .with_float(5.0)
+            .to_std140_bytes();
+        assert_eq!(floats(&bytes), vec![1.0, 0.0, 0.0, 0.0, 2.0, 3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn vec4_is_aligned_to_16_bytes() {
+        let bytes = UniformBlock::new()
+            .with_vec2([1.0, 2.0])
+            .with_vec4([3.0, 4.0, 5.0, 6.0])
+            .to_std140_bytes();
+        assert_eq!(floats(&bytes), vec![1.0, 2.0, 0.0, 0.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn mat4_is_packed_column_by_column() {
+        let columns = [
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 10.0, 11.0, 12.0],
+            [13.0, 14.0, 15.0, 16.0],
+        ];
+        let bytes = UniformBlock::new().with_float(0.5).with_mat4(columns).to_std140_bytes();
+
+        let mut expected = vec![0.5, 0.0, 0.0, 0.0];
+        expected.extend((1..=16).map(|value| value as f32));
+        assert_eq!(floats(&bytes), expected);
+    }
+
+    #[test]
+    fn values_are_little_endian() {
+        let bytes = UniformBlock::new().with_float(1.0).to_std140_bytes();
+        assert_eq!(&bytes[..4], &[0x00, 0x00, 0x80, 0x3f]);
+    }
+}
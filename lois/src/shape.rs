@@ -0,0 +1,175 @@
+use crate::{
+    batch::BlendMode,
+    commons::{Color, FRect, Point},
+    graphics::DrawShapesCommand,
+    mesh::MeshVertex,
+    texture::Texture,
+    transform::Transform2D,
+};
+
+pub struct ShapeBatch<'a> {
+    vertices: &'a mut Vec<MeshVertex>,
+    indices: &'a mut Vec<u32>,
+    command: &'a mut DrawShapesCommand,
+    transform: Transform2D,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ShapeBatchOptions {
+    pub target: Option<Texture>,
+    pub blend_mode: BlendMode,
+}
+
+impl<'a> ShapeBatch<'a> {
+    pub(crate) fn new(
+        transform: Transform2D,
+        vertices: &'a mut Vec<MeshVertex>,
+        indices: &'a mut Vec<u32>,
+        command: &'a mut DrawShapesCommand,
+    ) -> Self {
+        Self {
+            vertices,
+            indices,
+            command,
+            transform,
+        }
+    }
+
+    pub fn fill_rect(self, rect: FRect, color: Color) -> Self {
+        let FRect { x, y, w, h } = rect;
+        self.fill_convex(
+            &[
+                Point::new(x, y),
+                Point::new(x + w, y),
+                Point::new(x + w, y + h),
+                Point::new(x, y + h),
+            ],
+            color,
+        )
+    }
+
+    pub fn stroke_rect(self, rect: FRect, thickness: f32, color: Color) -> Self {
+        let FRect { x, y, w, h } = rect;
+        let thickness = thickness.min(w / 2.0).min(h / 2.0);
+        let inner_height = h - 2.0 * thickness;
+        self.fill_rect(FRect::new(x, y, w, thickness), color)
+            .fill_rect(FRect::new(x, y + h - thickness, w, thickness), color)
+            .fill_rect(FRect::new(x, y + thickness, thickness, inner_height), color)
+            .fill_rect(FRect::new(x + w - thickness, y + thickness, thickness, inner_height), color)
+    }
+
+    pub fn line(self, from: Point, to: Point, thickness: f32, color: Color) -> Self {
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        let length = from.distance_from(to);
+        if length <= 0.0 {
+            return self;
+        }
+        let (nx, ny) = (-dy / length * thickness / 2.0, dx / length * thickness / 2.0);
+        self.fill_convex(
+            &[
+                Point::new(from.x + nx, from.y + ny),
+                Point::new(to.x + nx, to.y + ny),
+                Point::new(to.x - nx, to.y - ny),
+                Point::new(from.x - nx, from.y - ny),
+            ],
+            color,
+        )
+    }
+
+    pub fn fill_circle(self, center: Point, radius: f32, segments: u32, color: Color) -> Self {
+        let points = circle_points(center, radius, segments);
+        self.fill_convex(&points, color)
+    }
+
+    pub fn stroke_circle(
+        self,
+        center: Point,
+        radius: f32,
+        thickness: f32,
+        segments: u32,
+        color: Color,
+    ) -> Self {
+        let outer = circle_points(center, radius, segments);
+        let inner = circle_points(center, (radius - thickness).max(0.0), segments);
+        self.stroke_ring(&outer, &inner, color)
+    }
+
+    pub fn fill_polygon(self, points: &[Point], color: Color) -> Self {
+        self.fill_convex(points, color)
+    }
+
+    pub fn stroke_polygon(self, points: &[Point], thickness: f32, color: Color) -> Self {
+        let mut batch = self;
+        for (index, &from) in points.iter().enumerate() {
+            let to = points[(index + 1) % points.len()];
+            batch = batch.line(from, to, thickness, color);
+        }
+        batch
+    }
+
+    fn fill_convex(mut self, points: &[Point], color: Color) -> Self {
+        if points.len() < 3 {
+            return self;
+        }
+        let first = self.vertices.len() as u32;
+        for &point in points {
+            self.push_vertex(point, color);
+        }
+        for index in 1..points.len() as u32 - 1 {
+            self.indices.extend_from_slice(&[first, first + index, first + index + 1]);
+        }
+        self.command.range.end = self.indices.len();
+        self
+    }
+
+    fn stroke_ring(mut self, outer: &[Point], inner: &[Point], color: Color) -> Self {
+        let first = self.vertices.len() as u32;
+        let count = outer.len() as u32;
+        for (&outer, &inner) in outer.iter().zip(inner.iter()) {
+            self.push_vertex(outer, color);
+            self.push_vertex(inner, color);
+        }
+        for index in 0..count {
+            let next = (index + 1) % count;
+            let (outer, inner) = (first + 2 * index, first + 2 * index + 1);
+            let (next_outer, next_inner) = (first + 2 * next, first + 2 * next + 1);
+            self.indices
+                .extend_from_slice(&[outer, next_outer, next_inner, outer, next_inner, inner]);
+        }
+        self.command.range.end = self.indices.len();
+        self
+    }
+
+    #[inline(always)]
+    fn push_vertex(&mut self, position: Point, color: Color) {
+        let position = self.transform.transform_point(position);
+        let vertex = MeshVertex::new(position, Point::default(), color);
+        self.vertices.push(MeshVertex {
+            color: self.command.blend_mode.tint(vertex.color),
+            ..vertex
+        });
+    }
+}
+
+impl ShapeBatchOptions {
+    pub fn new(target: Option<Texture>) -> Self {
+        Self {
+            target,
+            blend_mode: BlendMode::default(),
+        }
+    }
+
+    pub fn with_blend_mode(self, blend_mode: BlendMode) -> Self {
+        Self { blend_mode, ..self }
+    }
+}
+
+fn circle_points(center: Point, radius: f32, segments: u32) -> Vec<Point> {
+    let segments = segments.max(3);
+    (0..segments)
+        .map(|segment| {
+            let angle = segment as f32 / segments as f32 * std::f32::consts::TAU;
+            Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin())
+        })
+        .collect()
+}
@@ -0,0 +1,208 @@
+use crate::{graphics::{DrawCommand, DrawTextureBatchCommand}, quad::Quad};
+
+// Stably reorders quads by layer. Clear commands and target changes act as barriers,
+// so quads never move across them; consecutive quads sharing a batch state are merged
+// back into a single command.
+pub(crate) fn sort_by_layer(commands: &mut Vec<DrawCommand>, quads: &mut Vec<Quad>, layers: &mut Vec<i32>) {
+    let unsorted = std::mem::take(commands);
+    let mut sorted = Sorted {
+        commands,
+        quads: Vec::with_capacity(quads.len()),
+        layers: Vec::with_capacity(layers.len()),
+    };
+    let mut segment: Vec<(&DrawTextureBatchCommand, usize)> = Vec::new();
+
+    for command in unsorted.iter() {
+        match command {
+            DrawCommand::DrawTextureBatch(command) => {
+                if let Some((first, _)) = segment.first() {
+                    if first.target != command.target {
+                        sorted.flush(&mut segment, quads, layers);
+                    }
+                }
+                segment.extend(command.range.clone().map(|index| (command, index)));
+            }
+            command => {
+                sorted.flush(&mut segment, quads, layers);
+                sorted.commands.push(command.clone());
+            }
+        }
+    }
+    sorted.flush(&mut segment, quads, layers);
+
+    *quads = sorted.quads;
+    *layers = sorted.layers;
+}
+
+struct Sorted<'a> {
+    commands: &'a mut Vec<DrawCommand>,
+    quads: Vec<Quad>,
+    layers: Vec<i32>,
+}
+
+impl<'a> Sorted<'a> {
+    fn flush(&mut self, segment: &mut Vec<(&DrawTextureBatchCommand, usize)>, quads: &[Quad], layers: &[i32]) {
+        segment.sort_by_key(|&(_, index)| layers[index]);
+
+        for (command, index) in segment.drain(..) {
+            let position = self.quads.len();
+            match self.commands.last_mut() {
+                Some(DrawCommand::DrawTextureBatch(last))
+                    if last.range.end == position && last.batches_with(command) =>
+                {
+                    last.range.end += 1;
+                }
+                _ => self.commands.push(DrawCommand::DrawTextureBatch(DrawTextureBatchCommand {
+                    range: position..position + 1,
+                    ..command.clone()
+                })),
+            }
+            self.quads.push(quads[index]);
+            self.layers.push(layers[index]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commons::{Color, FRect, ViewSize},
+        graphics::{ClearCommand, DrawOptions},
+        texture::Texture,
+        transform::Transform2D,
+    };
+
+    #[derive(Default)]
+    struct Frame {
+        commands: Vec<DrawCommand>,
+        quads: Vec<Quad>,
+        layers: Vec<i32>,
+    }
+
+    impl Frame {
+        fn batch(&mut self, texture: u32, target: Option<u32>, layers: &[i32]) {
+            let start = self.quads.len();
+            for &layer in layers {
+                // Quads are told apart by their x position, which records the draw order.
+                let options = DrawOptions {
+                    dest_rect: Some(FRect::new(self.quads.len() as f32, 0.0, 1.0, 1.0)),
+                    layer,
+                    ..Default::default()
+                };
+                self.quads.push(Quad::new(ViewSize::new(100, 100), ViewSize::new(1, 1), Transform2D::IDENTITY, options));
+                self.layers.push(layer);
+            }
+            self.commands.push(DrawCommand::DrawTextureBatch(DrawTextureBatchCommand {
+                texture: Texture::new(texture),
+                target: target.map(Texture::new),
+                blend_mode: Default::default(),
+                shader: None,
+                uniforms: None,
+                sampler: None,
+                camera: None,
+                scissor: None,
+                range: start..self.quads.len(),
+            }));
+        }
+
+        fn clear(&mut self) {
+            self.commands.push(DrawCommand::Clear(ClearCommand {
+                target: None,
+                color: Color::BLACK,
+            }));
+        }
+
+        fn sort(&mut self) {
+            sort_by_layer(&mut self.commands, &mut self.quads, &mut self.layers);
+        }
+
+        // Texture id and drawn quads of every batch, with `None` standing for other commands.
+        fn summary(&self) -> Vec<Option<(u32, Vec<usize>)>> {
+            self.commands
+                .iter()
+                .map(|command| match command {
+                    DrawCommand::DrawTextureBatch(command) => Some((
+                        command.texture.id(),
+                        self.quads[command.range.clone()]
+                            .iter()
+                            .map(|quad| quad.bounds().x as usize)
+                            .collect(),
+                    )),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn sorts_quads_by_layer_across_batches() {
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[2, 0]);
+        frame.batch(2, None, &[1]);
+
+        frame.sort();
+
+        assert_eq!(frame.summary(), vec![Some((1, vec![1])), Some((2, vec![2])), Some((1, vec![0]))]);
+        assert_eq!(frame.layers, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn keeps_draw_order_within_a_layer() {
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[1, 0, 1]);
+        frame.batch(2, None, &[0, 1]);
+
+        frame.sort();
+
+        assert_eq!(
+            frame.summary(),
+            vec![
+                Some((1, vec![1])),
+                Some((2, vec![3])),
+                Some((1, vec![0, 2])),
+                Some((2, vec![4])),
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_consecutive_quads_of_the_same_batch() {
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[0]);
+        frame.batch(2, None, &[1]);
+        frame.batch(1, None, &[0]);
+
+        frame.sort();
+
+        assert_eq!(frame.summary(), vec![Some((1, vec![0, 2])), Some((2, vec![1]))]);
+    }
+
+    #[test]
+    fn clears_are_barriers() {
+        let mut frame = Frame::default();
+        frame.batch(1, None, &[1]);
+        frame.clear();
+        frame.batch(1, None, &[0]);
+
+        frame.sort();
+
+        assert_eq!(frame.summary(), vec![Some((1, vec![0])), None, Some((1, vec![1]))]);
+        assert_eq!(frame.layers, vec![1, 0]);
+    }
+
+    #[test]
+    fn target_changes_are_barriers() {
+        let mut frame = Frame::default();
+        frame.batch(1, Some(5), &[1]);
+        frame.batch(2, None, &[0]);
+        frame.batch(3, Some(5), &[0]);
+
+        frame.sort();
+
+        assert_eq!(
+            frame.summary(),
+            vec![Some((1, vec![0])), Some((2, vec![1])), Some((3, vec![2]))]
+        );
+    }
+}
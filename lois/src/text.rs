@@ -0,0 +1,464 @@
+use std::{collections::HashMap, convert::TryInto};
+
+use crate::{
+    backend::Backend,
+    batch::TextureBatchOptions,
+    commons::{Color, FRect, Point, Rect, ViewSize},
+    error::{Error, Result},
+    graphics::{DrawOptions, Graphics},
+    layout::{LayoutOptions, TextLayout, TextMetrics},
+    texture::{Texture, TextureLoadOptions},
+};
+
+const BINARY_MAGIC: &[u8] = b"BMF";
+const BINARY_VERSION: u8 = 3;
+const MAX_PAGES: i32 = 256;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Glyph {
+    pub id: u32,
+    pub rect: Rect,
+    pub x_offset: i32,
+    pub y_offset: i32,
+    pub x_advance: i32,
+    pub page: usize,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Kerning {
+    pub first: u32,
+    pub second: u32,
+    pub amount: i32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FontDescriptor {
+    pub face: Option<String>,
+    pub size: i32,
+    pub line_height: u32,
+    pub base: u32,
+    pub scale: ViewSize,
+    pub pages: Vec<String>,
+    pub glyphs: Vec<Glyph>,
+    pub kernings: Vec<Kerning>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ScaledBitmapFont<'a> {
+    font: &'a BitmapFont,
+    scale: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct BitmapFont {
+    line_height: u32,
+    base: u32,
+    pages: Vec<Texture>,
+    glyphs: HashMap<char, Glyph>,
+    kernings: HashMap<(char, char), i32>,
+}
+
+impl FontDescriptor {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.starts_with(BINARY_MAGIC) {
+            Self::parse_binary(data)
+        } else {
+            let text = std::str::from_utf8(data)
+                .map_err(|_| Error::InvalidFont("descriptor is not valid UTF-8".to_string()))?;
+            Self::parse_text(text)
+        }
+    }
+
+    pub fn parse_text(text: &str) -> Result<Self> {
+        let mut descriptor = Self::default();
+        let mut has_common = false;
+
+        for line in text.lines() {
+            let mut tokens = tokenize(line).into_iter();
+            let tag = match tokens.next() {
+                Some(tag) => tag,
+                None => continue,
+            };
+            let attributes: HashMap<&str, &str> = tokens
+                .filter_map(|token| {
+                    let mut parts = token.splitn(2, '=');
+                    Some((parts.next()?, parts.next()?))
+                })
+                .collect();
+            let get = |key: &str| -> Result<i32> {
+                attributes
+                    .get(key)
+                    .ok_or_else(|| Error::InvalidFont(format!("`{}` is missing `{}`", tag, key)))?
+                    .parse()
+                    .map_err(|_| Error::InvalidFont(format!("`{}` has an invalid `{}`", tag, key)))
+            };
+
+            match tag {
+                "info" => {
+                    descriptor.face = attributes.get("face").map(|face| face.trim_matches('"').to_string());
+                    descriptor.size = get("size")?;
+                }
+                "common" => {
+                    descriptor.line_height = get("lineHeight")? as u32;
+                    descriptor.base = get("base")? as u32;
+                    descriptor.scale = ViewSize::new(get("scaleW")? as u32, get("scaleH")? as u32);
+                    has_common = true;
+                }
+                "page" => {
+                    let id = get("id")?;
+                    if !(0..MAX_PAGES).contains(&id) {
+                        return Err(Error::InvalidFont("`page` has an invalid `id`".to_string()));
+                    }
+                    let id = id as usize;
+                    let file = attributes
+                        .get("file")
+                        .ok_or_else(|| Error::InvalidFont("`page` is missing `file`".to_string()))?
+                        .trim_matches('"')
+                        .to_string();
+                    if descriptor.pages.len() <= id {
+                        descriptor.pages.resize(id + 1, String::new());
+                    }
+                    descriptor.pages[id] = file;
+                }
+                "char" => descriptor.glyphs.push(Glyph {
+                    id: get("id")? as u32,
+                    rect: Rect::new(get("x")?, get("y")?, get("width")? as u32, get("height")? as u32),
+                    x_offset: get("xoffset")?,
+                    y_offset: get("yoffset")?,
+                    x_advance: get("xadvance")?,
+                    page: get("page")? as usize,
+                }),
+                "kerning" => descriptor.kernings.push(Kerning {
+                    first: get("first")? as u32,
+                    second: get("second")? as u32,
+                    amount: get("amount")?,
+                }),
+                _ => {}
+            }
+        }
+
+        if !has_common {
+            return Err(Error::InvalidFont("descriptor has no `common` block".to_string()));
+        }
+        if let Some(id) = descriptor.pages.iter().position(String::is_empty) {
+            return Err(Error::InvalidFont(format!("page {} is missing", id)));
+        }
+        Ok(descriptor)
+    }
+
+    pub fn parse_binary(data: &[u8]) -> Result<Self> {
+        if !data.starts_with(BINARY_MAGIC) || data.get(3) != Some(&BINARY_VERSION) {
+            return Err(Error::InvalidFont("unsupported binary descriptor version".to_string()));
+        }
+
+        let mut descriptor = Self::default();
+        let mut has_common = false;
+        let mut reader = Reader::new(&data[4..]);
+
+        while !reader.is_empty() {
+            let tag = reader.u8()?;
+            let size = reader.u32()? as usize;
+            let mut block = Reader::new(reader.bytes(size)?);
+
+            match tag {
+                1 => {
+                    descriptor.size = block.i16()? as i32;
+                    block.bytes(12)?;
+                    descriptor.face = Some(block.string()?);
+                }
+                2 => {
+                    descriptor.line_height = block.u16()? as u32;
+                    descriptor.base = block.u16()? as u32;
+                    descriptor.scale = ViewSize::new(block.u16()? as u32, block.u16()? as u32);
+                    has_common = true;
+                }
+                3 => {
+                    while !block.is_empty() {
+                        descriptor.pages.push(block.string()?);
+                    }
+                }
+                4 => {
+                    while !block.is_empty() {
+                        let id = block.u32()?;
+                        let (x, y) = (block.u16()? as i32, block.u16()? as i32);
+                        let (width, height) = (block.u16()? as u32, block.u16()? as u32);
+                        descriptor.glyphs.push(Glyph {
+                            id,
+                            rect: Rect::new(x, y, width, height),
+                            x_offset: block.i16()? as i32,
+                            y_offset: block.i16()? as i32,
+                            x_advance: block.i16()? as i32,
+                            page: block.u8()? as usize,
+                        });
+                        block.u8()?;
+                    }
+                }
+                5 => {
+                    while !block.is_empty() {
+                        descriptor.kernings.push(Kerning {
+                            first: block.u32()?,
+                            second: block.u32()?,
+                            amount: block.i16()? as i32,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if !has_common {
+            return Err(Error::InvalidFont("descriptor has no `common` block".to_string()));
+        }
+        Ok(descriptor)
+    }
+}
+
+impl BitmapFont {
+    // `load_page` is called with the file name of every page and must return its pixels
+    // in the default texture format: tightly packed 8-bit BGRA rows, top to bottom, so
+    // `4 * scale.width * scale.height` bytes as declared by the descriptor's `common` block.
+    pub fn load<B, F>(
+        graphics: &mut Graphics<B>,
+        descriptor: &FontDescriptor,
+        mut load_page: F,
+    ) -> Result<Self>
+    where
+        B: Backend,
+        F: FnMut(&str) -> Result<Vec<u8>>,
+    {
+        let mut pages = Vec::with_capacity(descriptor.pages.len());
+        for file in descriptor.pages.iter() {
+            let page = load_page(file).and_then(|data| {
+                graphics.load_texture(TextureLoadOptions {
+                    name: Some(file),
+                    data: Some(&data),
+                    size: descriptor.scale,
+                    ..Default::default()
+                })
+            });
+            match page {
+                Ok(page) => pages.push(page),
+                Err(err) => {
+                    for page in pages {
+                        graphics.unload_texture(page);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        let glyphs = descriptor
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.page < pages.len())
+            .filter_map(|glyph| Some((std::char::from_u32(glyph.id)?, *glyph)))
+            .collect();
+        let kernings = descriptor
+            .kernings
+            .iter()
+            .filter_map(|kerning| {
+                let first = std::char::from_u32(kerning.first)?;
+                let second = std::char::from_u32(kerning.second)?;
+                Some(((first, second), kerning.amount))
+            })
+            .collect();
+
+        Ok(Self {
+            line_height: descriptor.line_height,
+            base: descriptor.base,
+            pages,
+            glyphs,
+            kernings,
+        })
+    }
+
+    pub fn unload<B>(self, graphics: &mut Graphics<B>)
+    where
+        B: Backend,
+    {
+        for page in self.pages {
+            graphics.unload_texture(page);
+        }
+    }
+
+    pub fn line_height(&self) -> u32 {
+        self.line_height
+    }
+
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    pub fn pages(&self) -> &[Texture] {
+        &self.pages
+    }
+
+    pub fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs.get(&character)
+    }
+
+    pub fn kerning(&self, first: char, second: char) -> i32 {
+        self.kernings.get(&(first, second)).copied().unwrap_or(0)
+    }
+
+    pub fn scaled(&self, scale: f32) -> ScaledBitmapFont<'_> {
+        ScaledBitmapFont { font: self, scale }
+    }
+}
+
+impl TextMetrics for ScaledBitmapFont<'_> {
+    fn line_height(&self) -> f32 {
+        self.font.line_height as f32 * self.scale
+    }
+
+    fn advance(&self, character: char) -> f32 {
+        self.font
+            .glyph(character)
+            .map(|glyph| glyph.x_advance as f32 * self.scale)
+            .unwrap_or(0.0)
+    }
+
+    fn kerning(&self, first: char, second: char) -> f32 {
+        self.font.kerning(first, second) as f32 * self.scale
+    }
+}
+
+pub fn draw_text<B>(
+    graphics: &mut Graphics<B>,
+    target: Option<Texture>,
+    font: &BitmapFont,
+    text: &str,
+    position: Point,
+    color: Color,
+    scale: f32,
+) -> Result<()>
+where
+    B: Backend,
+{
+    let options = LayoutOptions {
+        bounds: FRect::new(position.x, position.y, 0.0, 0.0),
+        ..Default::default()
+    };
+    let layout = TextLayout::new(&font.scaled(scale), text, options);
+    draw_layout(graphics, target, font, &layout, color, scale)
+}
+
+pub fn draw_layout<B>(
+    graphics: &mut Graphics<B>,
+    target: Option<Texture>,
+    font: &BitmapFont,
+    layout: &TextLayout,
+    color: Color,
+    scale: f32,
+) -> Result<()>
+where
+    B: Backend,
+{
+    let mut glyphs = layout
+        .glyphs()
+        .iter()
+        .filter_map(|layout_glyph| {
+            let glyph = font.glyph(layout_glyph.character).filter(|glyph| glyph.rect.w > 0 && glyph.rect.h > 0)?;
+            let dest_rect = FRect::new(
+                layout_glyph.rect.x + glyph.x_offset as f32 * scale,
+                layout_glyph.rect.y + glyph.y_offset as f32 * scale,
+                glyph.rect.w as f32 * scale,
+                glyph.rect.h as f32 * scale,
+            );
+            let options = DrawOptions {
+                src_rect: Some(glyph.rect.into()),
+                dest_rect: Some(dest_rect),
+                color,
+                ..Default::default()
+            };
+            Some((glyph.page, options))
+        })
+        .peekable();
+
+    // Consecutive glyphs on the same page share a batch.
+    while let Some((page, options)) = glyphs.next() {
+        let mut batch = graphics
+            .new_batch(TextureBatchOptions::new(font.pages[page], target))?
+            .draw(options);
+        while let Some((_, options)) = glyphs.next_if(|&(next_page, _)| next_page == page) {
+            batch = batch.draw(options);
+        }
+    }
+
+    Ok(())
+}
+
+// Splits a text descriptor line on whitespace, keeping quoted values such as
+// `face="Open Sans"` in a single token.
+fn tokenize(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => quoted = !quoted,
+            character if character.is_whitespace() && !quoted => {
+                if let Some(start) = start.take() {
+                    tokens.push(&line[start..index]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(index);
+    }
+    if let Some(start) = start {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(Error::InvalidFont("unexpected end of binary descriptor".to_string()));
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self
+            .data
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(|| Error::InvalidFont("unterminated string in binary descriptor".to_string()))?;
+        let string = String::from_utf8_lossy(self.bytes(len)?).into_owned();
+        self.bytes(1)?;
+        Ok(string)
+    }
+}
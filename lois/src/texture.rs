@@ -1,6 +1,6 @@
 use crate::commons::ViewSize;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Texture(u32);
 
 #[derive(Copy, Clone, Debug)]
@@ -9,6 +9,7 @@ pub struct TextureQuery<'a> {
     pub size: ViewSize,
     pub format: TextureFormat,
     pub usage: TextureUsage,
+    pub sampler: SamplerOptions,
 }
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -18,15 +19,48 @@ pub struct TextureLoadOptions<'a> {
     pub size: ViewSize,
     pub format: TextureFormat,
     pub usage: TextureUsage,
+    pub sampler: SamplerOptions,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SamplerOptions {
+    pub min_filter: FilterMode,
+    pub mag_filter: FilterMode,
+    pub mipmap_filter: FilterMode,
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FilterMode {
+    #[default]
+    Nearest,
+    Linear,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AddressMode {
+    #[default]
+    ClampToEdge,
+    Repeat,
+    MirrorRepeat,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum TextureFormat {
+    #[default]
     Bgra8UnormSrgb,
+    Bgra8Unorm,
+    Rgba8UnormSrgb,
+    Rgba8Unorm,
+    R8Unorm,
+    Rg8Unorm,
+    Rgba16Float,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub enum TextureUsage {
+    #[default]
     Default,
     RenderTarget,
 }
@@ -41,14 +75,49 @@ impl Texture {
     }
 }
 
-impl Default for TextureFormat {
-    fn default() -> Self {
-        Self::Bgra8UnormSrgb
+impl TextureFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            TextureFormat::R8Unorm => 1,
+            TextureFormat::Rg8Unorm => 2,
+            TextureFormat::Bgra8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Rgba8Unorm => 4,
+            TextureFormat::Rgba16Float => 8,
+        }
     }
 }
 
-impl Default for TextureUsage {
-    fn default() -> Self {
-        Self::Default
+impl SamplerOptions {
+    pub fn new(filter: FilterMode, address_mode: AddressMode) -> Self {
+        Self {
+            min_filter: filter,
+            mag_filter: filter,
+            mipmap_filter: filter,
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+        }
+    }
+
+    pub fn linear() -> Self {
+        Self::new(FilterMode::Linear, AddressMode::ClampToEdge)
+    }
+
+    pub fn with_filter(self, filter: FilterMode) -> Self {
+        Self {
+            min_filter: filter,
+            mag_filter: filter,
+            mipmap_filter: filter,
+            ..self
+        }
+    }
+
+    pub fn with_address_mode(self, address_mode: AddressMode) -> Self {
+        Self {
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            ..self
+        }
     }
 }
@@ -0,0 +1,70 @@
+use std::ops::Mul;
+
+use glam::{Mat3, Mat4, Vec2};
+
+use crate::commons::Point;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform2D(Mat3);
+
+impl Transform2D {
+    pub const IDENTITY: Transform2D = Transform2D(Mat3::IDENTITY);
+
+    #[inline(always)]
+    pub fn from_translation(x: f32, y: f32) -> Self {
+        Self(Mat3::from_translation(Vec2::new(x, y)))
+    }
+
+    #[inline(always)]
+    pub fn from_rotation(angle: f32) -> Self {
+        Self(Mat3::from_rotation_z(angle))
+    }
+
+    #[inline(always)]
+    pub fn from_scale(x: f32, y: f32) -> Self {
+        Self(Mat3::from_scale(Vec2::new(x, y)))
+    }
+
+    pub fn from_rotation_around(angle: f32, center: Point) -> Self {
+        Self::from_translation(center.x, center.y)
+            * Self::from_rotation(angle)
+            * Self::from_translation(-center.x, -center.y)
+    }
+
+    #[inline(always)]
+    pub fn inverse(self) -> Self {
+        Self(self.0.inverse())
+    }
+
+    #[inline(always)]
+    pub fn transform_point(self, point: Point) -> Point {
+        let point = self.0.transform_point2(Vec2::new(point.x, point.y));
+        Point::new(point.x, point.y)
+    }
+
+    #[inline(always)]
+    pub(crate) fn to_mat4(self) -> Mat4 {
+        let [x_axis, y_axis, translation] = self.0.to_cols_array_2d();
+        Mat4::from_cols_array_2d(&[
+            [x_axis[0], x_axis[1], 0.0, 0.0],
+            [y_axis[0], y_axis[1], 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [translation[0], translation[1], 0.0, 1.0],
+        ])
+    }
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Transform2D {
+    type Output = Transform2D;
+
+    #[inline(always)]
+    fn mul(self, rhs: Transform2D) -> Self::Output {
+        Self(self.0 * rhs.0)
+    }
+}
@@ -0,0 +1,121 @@
+use lois::{
+    commons::{Rect, ViewSize},
+    text::{FontDescriptor, Glyph, Kerning},
+};
+
+const TEXT: &str = include_str!("fixtures/font.fnt");
+const BINARY: &[u8] = include_bytes!("fixtures/font_binary.fnt");
+
+fn assert_fixture(descriptor: &FontDescriptor) {
+    assert_eq!(descriptor.face.as_deref(), Some("Test Sans"));
+    assert_eq!(descriptor.size, 16);
+    assert_eq!(descriptor.line_height, 18);
+    assert_eq!(descriptor.base, 14);
+    assert_eq!(descriptor.scale, ViewSize::new(64, 32));
+    assert_eq!(descriptor.pages, vec!["font_0.png", "font_1.png"]);
+    assert_eq!(
+        descriptor.glyphs,
+        vec![
+            Glyph {
+                id: 65,
+                rect: Rect::new(0, 0, 8, 10),
+                x_offset: 0,
+                y_offset: 4,
+                x_advance: 9,
+                page: 0,
+            },
+            Glyph {
+                id: 66,
+                rect: Rect::new(8, 0, 7, 10),
+                x_offset: 1,
+                y_offset: 4,
+                x_advance: 8,
+                page: 0,
+            },
+            Glyph {
+                id: 32,
+                rect: Rect::new(0, 0, 0, 0),
+                x_offset: 0,
+                y_offset: 0,
+                x_advance: 4,
+                page: 1,
+            },
+        ]
+    );
+    assert_eq!(
+        descriptor.kernings,
+        vec![Kerning {
+            first: 65,
+            second: 66,
+            amount: -1,
+        }]
+    );
+}
+
+#[test]
+fn parses_text_descriptor() {
+    assert_fixture(&FontDescriptor::parse_text(TEXT).unwrap());
+    assert_fixture(&FontDescriptor::parse(TEXT.as_bytes()).unwrap());
+}
+
+#[test]
+fn parses_binary_descriptor() {
+    assert_fixture(&FontDescriptor::parse_binary(BINARY).unwrap());
+    assert_fixture(&FontDescriptor::parse(BINARY).unwrap());
+}
+
+#[test]
+fn rejects_truncated_text_descriptor() {
+    let truncated = &TEXT[..TEXT.find("scaleH").unwrap()];
+    assert!(FontDescriptor::parse_text(truncated).is_err());
+
+    let truncated = TEXT.replace("xadvance=8     page=0  chnl=15", "xadvance=");
+    assert!(FontDescriptor::parse_text(&truncated).is_err());
+
+    for (index, _) in TEXT.char_indices() {
+        let _ = FontDescriptor::parse_text(&TEXT[..index]);
+    }
+}
+
+#[test]
+fn rejects_malformed_text_descriptor() {
+    assert!(FontDescriptor::parse_text("").is_err());
+    assert!(FontDescriptor::parse_text("info face=\"Test Sans\" size=16").is_err());
+    assert!(FontDescriptor::parse_text(&TEXT.replace("lineHeight=18", "lineHeight=tall")).is_err());
+    assert!(FontDescriptor::parse_text(&TEXT.replace("page id=1", "page id=-1")).is_err());
+    assert!(FontDescriptor::parse_text(&TEXT.replace("page id=1", "page id=2147483647")).is_err());
+    assert!(FontDescriptor::parse_text(&TEXT.replace(" file=\"font_1.png\"", "")).is_err());
+    assert!(FontDescriptor::parse_text(&TEXT.replace("page id=1", "page id=2")).is_err());
+    assert!(FontDescriptor::parse_text(&TEXT.replace("page id=0 file=\"font_0.png\"\n", "")).is_err());
+    assert!(FontDescriptor::parse(&[0xff, 0xfe, 0x00]).is_err());
+}
+
+#[test]
+fn rejects_truncated_binary_descriptor() {
+    // Cuts inside the header, the info block, the char block and the kerning block.
+    for &len in &[0, 2, 4, 20, 100, BINARY.len() - 1] {
+        assert!(FontDescriptor::parse_binary(&BINARY[..len]).is_err(), "length {}", len);
+    }
+
+    for len in 0..BINARY.len() {
+        let _ = FontDescriptor::parse_binary(&BINARY[..len]);
+    }
+}
+
+#[test]
+fn rejects_malformed_binary_descriptor() {
+    let mut version = BINARY.to_vec();
+    version[3] = 2;
+    assert!(FontDescriptor::parse_binary(&version).is_err());
+
+    // The info block's face name loses its terminator.
+    let mut unterminated = BINARY[..33].to_vec();
+    unterminated[32] = b'!';
+    assert!(FontDescriptor::parse_binary(&unterminated).is_err());
+
+    // A char block one byte short of a whole record.
+    let mut chars = BINARY[..80].to_vec();
+    chars.extend_from_slice(&[4, 19, 0, 0, 0]);
+    chars.extend_from_slice(&BINARY[85..104]);
+    assert!(FontDescriptor::parse_binary(&chars).is_err());
+}